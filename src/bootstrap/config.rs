@@ -7,6 +7,11 @@ use super::error::{Error,Result};
 pub const GETH_CLIQUE : & str = "geth_clique";
 pub const GETH_POW    : & str = "geth_pow";
 pub const GETH_AUTO   : & str = "geth";
+pub const GETH_POS    : & str = "geth_pos";
+pub const PARITY      : & str = "parity";
+pub const AURA        : & str = "aura";
+pub const IBFT        : & str = "ibft";
+pub const QBFT        : & str = "qbft";
 
 #[derive(Debug, Deserialize)]
 pub struct NamedAddress {
@@ -34,21 +39,50 @@ pub struct Config {
     /// flag to store non-empty blocks
     pub db_store_neb : bool,
 
-    /// flag to store non-empty blocks
+    /// web3 endpoint, http(s)://, ws(s)://, ipc:// or ipc socket path
     pub web3_url: String,
 
+    /// web3 endpoints used when web3_url fails (optional)
+    pub web3_fallback_urls: Option<Vec<String>>,
+
     /// web3 client to use
     pub web3_client: String,
 
     /// flag is web3 is able to get internal transactions
     pub web3_itx: bool,
 
+    /// block where the ethash reward is reduced to 3 ether (optional, from the
+    ///   genesis config or mainnet by default)
+    pub pow_byzantium_block: Option<u64>,
+
+    /// block where the ethash reward is reduced to 2 ether (optional, from the
+    ///   genesis config or mainnet by default)
+    pub pow_constantinople_block: Option<u64>,
+
+    /// clique epoch length, to find the checkpoints with the signers list
+    pub clique_epoch: Option<u64>,
+
+    /// flag to verify the clique seals against the signers (optional)
+    pub clique_verify_seals: Option<bool>,
+
+    /// geth genesis.json whose allocations are imported as block 0 (optional)
+    pub genesis_path: Option<String>,
+
     /// flag to scan transactions
     pub scan: bool,
 
     /// when scan is true, the first block to scan
     pub scan_start_block: Option<u64>,
 
+    /// number of blocks the scan stays behind the last block (optional)
+    pub scan_confirmations: Option<u64>,
+
+    /// scan from the last block backwards to scan_start_block (optional)
+    pub scan_backwards: Option<bool>,
+
+    /// number of workers prefetching blocks when scanning (optional)
+    pub scan_workers: Option<usize>,
+
     /// network ip:port binding
     pub bind: String,
 
@@ -63,6 +97,22 @@ pub struct Config {
 }
 
 impl Config {
+
+    /// flag if internal transactions are read with the trace_ api, aura
+    ///   networks run on openethereum or nethermind
+    pub fn trace_api(&self) -> bool {
+        self.web3_client == PARITY || self.web3_client == AURA
+    }
+
+    /// all web3 endpoints, the preferred first
+    pub fn web3_urls(&self) -> Vec<String> {
+        let mut urls = vec![self.web3_url.clone()];
+        if let Some(fallback_urls) = &self.web3_fallback_urls {
+            urls.extend_from_slice(fallback_urls);
+        }
+        urls
+    }
+
     /// read the .toml configutation 
     pub fn read(path: &str) -> Result<Self> {
 
@@ -74,11 +124,29 @@ impl Config {
         let cfg : Config = toml::from_str(&contents)?;
         if cfg.web3_client != GETH_CLIQUE
            && cfg.web3_client != GETH_POW
-           && cfg.web3_client != GETH_AUTO {
-            Err(Error::InvalidOption(format!("only {} allowed in web3_client",GETH_CLIQUE)))
+           && cfg.web3_client != GETH_AUTO
+           && cfg.web3_client != GETH_POS
+           && cfg.web3_client != PARITY
+           && cfg.web3_client != AURA
+           && cfg.web3_client != IBFT
+           && cfg.web3_client != QBFT {
+            Err(Error::InvalidOption(format!("only {}, {}, {}, {}, {}, {}, {} or {} allowed in web3_client",
+                GETH_CLIQUE, GETH_POW, GETH_AUTO, GETH_POS, PARITY, AURA, IBFT, QBFT)))
+        } else if let Some(url) = cfg.web3_urls().into_iter().find(|url| !is_supported_url(url)) {
+            Err(Error::InvalidOption(format!("web3 url {} must be http(s)://, ws(s)://, ipc:// or an ipc socket path",
+                url)))
         } else {
             Ok(cfg)
         }
     }
 }
 
+/// check if the scheme of a web3 url is supported, urls without scheme are
+///   ipc socket paths
+fn is_supported_url(url: &str) -> bool {
+    match url.find("://") {
+        Some(pos) => ["http", "https", "ws", "wss", "ipc"].contains(&&url[..pos]),
+        None => true,
+    }
+}
+
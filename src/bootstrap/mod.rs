@@ -4,4 +4,4 @@ mod error;
 
 pub use self::error::{Error,Result};
 pub use self::staticres::{load_handlebars_templates,get_resource};
-pub use self::config::{Config,GETH_CLIQUE,GETH_POW,GETH_AUTO};
+pub use self::config::{Config,GETH_CLIQUE,GETH_POW,GETH_AUTO,GETH_POS,PARITY,AURA,IBFT,QBFT};
@@ -1,4 +1,6 @@
-use rocksdb::{Direction, IteratorMode, DB};
+use rocksdb::{Direction, IteratorMode, WriteBatch, DB};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
 use serde_cbor::{from_slice, to_vec};
 use web3::types::{Address, Block, Transaction, TransactionReceipt, H256};
 use rustc_hex::ToHex;
@@ -8,7 +10,8 @@ use super::types::*;
 use super::utils::*;
 use super::iterators::*;
 
-use super::super::eth::types::InternalTx;
+use super::super::eth::types::{BlockExt, GenesisAlloc, InternalTx, TxExt};
+use super::super::eth::geth::clique::{SealViolation, SignerChange, Snapshot};
 
 pub struct Options {
     pub store_itx : bool,
@@ -20,6 +23,7 @@ pub struct Options {
 pub struct AppDB {
     db: DB,
     opt: Options,
+    writer_lock: Mutex<()>,
 }
 
 /*
@@ -35,10 +39,30 @@ pub struct AppDB {
                                                         TxFrom(from_addr)
   AddrLinkCount <addr>                                  u64
   Block    <blockno>                                    cbor-encoded-block
+  BlockHash <blockno>                                   H256
   ContractAbi <addr>                                    cbor-encoded abi and compile params
   NonEmptyBlock <blockno>                               none
   NonEmptyBlockCount                                    u64
   NextBlock                                             u64
+  FirstBlock                                            u64
+  NetworkId                                             string
+  CliqueSnapshot <blockno>                              cbor-encoded-snapshot
+  CliqueSignerChange <blockno> <addr>                   cbor-encoded-signerchange
+  CliqueSeal <blockno>                                  cbor-encoded-seal
+  SealerBlock <addr> <blockno>                          none
+  SealerCount <addr> <counter>                          u64
+  SealerDay <addr> <day>                                u64
+  SealViolation <blockno>                               cbor-encoded-sealviolation
+  BlockExt <blockno>                                    cbor-encoded-blockext
+  AddrEvent <addr> <blockno> <kind> <index>             cbor-encoded-addrevent
+  AddrEventCount <addr>                                 u64
+  BlockAddrEvent <blockno> <addr> <kind> <index>        none
+  TxExt    <txhash>                                     cbor-encoded-txext
+  Uncle    <blockno> <index>                            cbor-encoded-block
+  GenesisAlloc <addr>                                   cbor-encoded-genesisalloc
+  GenesisAllocCount                                     u64
+  CliqueFirstBlock                                      u64
+  BlockTxLink <blockno> <addrlink-key>                  none, only without Tx
 
   if *inttx == 0 means a main transaction
   else           means an internal transaction num n
@@ -56,14 +80,23 @@ impl AppDB {
 
     /// open the datase
     pub fn open_default(path: &str, opt: Options) -> Result<AppDB> {
-        Ok(DB::open_default(path).map(|x| AppDB{ opt, db: x })?)
+        Ok(DB::open_default(path).map(|x| AppDB{ opt, db: x, writer_lock: Mutex::new(()) })?)
     }
 
-    /// add an address that has some relationship with a tx
-    pub fn add_addrtx_link(&self, addr: &Address, tx: &Transaction, inttxno : u64) -> Result<()> {        
-
-        // construct the key
+    /// create a writer to update the database atomically. Writers are
+    ///   serialized, it waits until the previous one is committed or dropped
+    pub fn writer(&self) -> BlockWriter {
+        BlockWriter {
+            _lock : self.writer_lock.lock().unwrap_or_else(|err| err.into_inner()),
+            appdb : self,
+            batch : WriteBatch::default(),
+            counters : HashMap::new(),
+            staged : HashMap::new(),
+        }
+    }
 
+    /// key of the link between an address and a tx
+    fn addrtx_link_key(addr: &Address, tx: &Transaction, inttxno : u64) -> Vec<u8> {
         let revblockno = u64_to_le(std::u64::MAX - tx.block_number.unwrap().low_u64());
         let revtxindex = u64_to_le(std::u64::MAX - tx.block_number.unwrap().low_u64());
         let revinttx = u64_to_le(std::u64::MAX - inttxno);
@@ -74,97 +107,42 @@ impl AppDB {
         key.extend_from_slice(&revtxindex);
         key.extend_from_slice(&tx.hash);
         key.extend_from_slice(&revinttx);
+        key
+    }
 
-        // add the link 
-        let zero : Vec<u8> = vec![];
-        self.db.put(&key.to_owned(), &zero)?;
-
-        // increment the number of links for this address
-        self.inc_addr_tx_links(&addr)?;
+    /// key of the index of the address links of a block, used to remove them
+    ///   when the transactions are not stored. An empty link_key marks that
+    ///   the links of the block are indexed
+    fn block_txlink_key(blockno: u64, link_key: &[u8]) -> Vec<u8> {
+        let mut key = vec![RecordType::BlockTxLink as u8];
+        key.extend_from_slice(&u64_to_le(blockno));
+        key.extend_from_slice(link_key);
+        key
+    }
 
-        Ok(())
+    /// check if the address links of a block are indexed, so they can be
+    ///   removed without the transactions
+    pub fn has_block_txlinks(&self, blockno: u64) -> Result<bool> {
+        Ok(self.db.get(&Self::block_txlink_key(blockno, &[]))?.is_some())
     }
-    
-    /// add all links from a transaction to an address
-    fn add_addrtx_links(&self, tx: &Transaction, from: Address, to:Option<Address>, contract:Option<Address>, int_tx_no : u64 )  -> Result<()> {
 
-        // check preconditon
-        if contract.is_none() && to.is_none() {
-            unreachable!("broken add_addrtx_links precondition")
-        }        
-        
-        // add the related links
-        if let Some(contract) = contract {
-            self.add_addrtx_link(&from,&tx,int_tx_no)?;
-            self.add_addrtx_link(&contract,&tx,int_tx_no)?;
-        } else {
-            let to = to.unwrap();
-            if from == to {
-                self.add_addrtx_link(&from,&tx,int_tx_no)?;
-            } else {
-                self.add_addrtx_link(&from,&tx,int_tx_no)?;
-                self.add_addrtx_link(&to,&tx,int_tx_no)?;
-            }
+    /// get the addresses linked by a transaction. Failed internal creates
+    ///   have no destination nor contract, so only the sender is linked
+    fn addrtx_links_addrs(from: Address, to:Option<Address>, contract:Option<Address>) -> Vec<Address> {
+        match (contract, to) {
+            (Some(contract), _) => vec![from, contract],
+            (None, Some(to)) if from != to => vec![from, to],
+            _ => vec![from],
         }
-        Ok(())
     }
 
-    /// add an internal transaction
-    fn add_itx(&self, tx: &Transaction, itx: &InternalTx, itx_no: u64) -> Result<()> {
-
-        // store the internal transaction 
+    /// key of an internal transaction
+    fn itx_key(txhash: &H256, itx_no: u64) -> Vec<u8> {
         let mut itx_k = vec![RecordType::IntTx as u8];
         let rev_itx_no = u64_to_le(std::u64::MAX - itx_no);
-        itx_k.extend_from_slice(&tx.hash);
+        itx_k.extend_from_slice(&txhash);
         itx_k.extend_from_slice(&rev_itx_no);
-        
-        self.db
-            .put(itx_k.as_slice(), to_vec(itx).unwrap().as_slice())?;
-
-        // store its addresslinks
-        self.add_addrtx_links(&tx,itx.from, itx.to, itx.contract,itx_no)?;
-
-        Ok(())
-    }
-
-    pub fn add_tx(&self, tx: &Transaction, tr: &TransactionReceipt, itxs : Option<&[InternalTx]>) -> Result<()> {
-
-        // check preconditon
-        if tx.to.is_none() && tr.contract_address.is_none() {
-            unreachable!("broken add_tx precondition")
-        }        
-
-        // only store tx if config flag is set
-        if self.opt.store_tx {
-
-            // store tx
-            let mut tx_k = vec![RecordType::Tx as u8];
-            tx_k.extend_from_slice(&tx.hash);
-            self.db
-                .put(tx_k.as_slice(), to_vec(tx).unwrap().as_slice())?;
-
-            // store receipt
-            let mut r_k = vec![RecordType::Receipt as u8];
-            r_k.extend_from_slice(&tx.hash);
-            self.db.put(r_k.as_slice(), to_vec(tr).unwrap().as_slice())?;
-
-            // store internal transactions
-            if self.opt.store_itx {
-                if let Some(itxs) = itxs {
-                    for (i, itx) in itxs.into_iter().enumerate() {
-                        self.add_itx(&tx,itx,i as u64 + 1)?;
-                    }
-                }
-            }
-        }
-
-        // only store address links if config flag is set
-        if self.opt.store_addr {
-            // TxLinks
-            self.add_addrtx_links(&tx,tx.from,tx.to,tr.contract_address,0)?;
-        }
-
-        Ok(())
+        itx_k
     }
 
     /// get a transaction
@@ -180,17 +158,28 @@ impl AppDB {
 
     /// get an internal transaction
     pub fn get_itx(&self, txhash: &H256, itx_no: u64) ->  Result<Option<InternalTx>> {
-        let mut itx_k = vec![RecordType::IntTx as u8];
-        let rev_itx_no = u64_to_le(std::u64::MAX - itx_no);
-        itx_k.extend_from_slice(&txhash);
-        itx_k.extend_from_slice(&rev_itx_no);
-          
+        let itx_k = Self::itx_key(txhash, itx_no);
         match self.db.get(&itx_k)? {
             None => Ok(None),
             Some(v) => Ok(Some(from_slice::<InternalTx>(&v)?))
         }
     }
 
+    /// key of the typed fields of a transaction
+    fn tx_ext_key(txhash: &H256) -> Vec<u8> {
+        let mut key = vec![RecordType::TxExt as u8];
+        key.extend_from_slice(&txhash);
+        key
+    }
+
+    /// get the typed fields of a transaction
+    pub fn get_tx_ext(&self, txhash: &H256) -> Result<Option<TxExt>> {
+        match self.db.get(&Self::tx_ext_key(txhash))? {
+            None => Ok(None),
+            Some(v) => Ok(Some(from_slice::<TxExt>(&v)?))
+        }
+    }
+
     /// get a receipt
     pub fn get_receipt(&self, txhash: &H256) -> Result<Option<TransactionReceipt>> {
         let mut rcpt_k = vec![RecordType::Receipt as u8];
@@ -202,29 +191,51 @@ impl AppDB {
         }
     }
 
-    /// add a new block
-    pub fn add_block(&self, block: &Block<H256>) -> Result<()> {
-        
-        if self.opt.store_tx {
-            // add the block
-            let mut b_k = vec![RecordType::Block as u8];
-            let block_no = u64_to_le(block.number.unwrap().low_u64());
-            b_k.extend_from_slice(&block_no);
+    /// key of a block hash
+    fn block_hash_key(blockno: u64) -> Vec<u8> {
+        let mut key = vec![RecordType::BlockHash as u8];
+        key.extend_from_slice(&u64_to_le(blockno));
+        key
+    }
 
-            self.db.put(b_k.as_slice(), to_vec(block)?.as_slice())?;
-        }
+    /// get the hash of an indexed block
+    #[allow(deprecated)]
+    pub fn get_block_hash(&self, blockno: u64) -> Result<Option<H256>> {
+        Ok(self
+            .db
+            .get(&Self::block_hash_key(blockno))?
+            .map(|bytes| H256::from_slice(&*bytes)))
+    }
 
-        if self.opt.store_neb && !block.transactions.is_empty() {
-            // annotate a non-empty-block
-            let mut neb_k = vec![RecordType::NonEmptyBlock as u8];
-            let block_no_rev = u64_to_le(std::u64::MAX - block.number.unwrap().low_u64());
-            neb_k.extend_from_slice(&block_no_rev);
-            self.db.put(neb_k.as_slice(), &[])?;
-            
-            // increment counter of non-empty-blocks
-            self.inc_u64(&[RecordType::NonEmptyBlockCount as u8])?;
+    /// get the blocks in the range [from,to) that have not been indexed. The
+    ///   blocks are checked if they are stored, the block hashes otherwise
+    pub fn missing_blocks(&self, from: u64, to: u64) -> Result<Vec<u64>> {
+        let prefix = if self.opt.store_tx {
+            vec![RecordType::Block as u8]
+        } else {
+            vec![RecordType::BlockHash as u8]
+        };
+        let mut start = prefix.clone();
+        start.extend_from_slice(&u64_to_le(from));
+        let iter = self
+            .db
+            .iterator(IteratorMode::From(&start, Direction::Forward));
+
+        let mut missing = Vec::new();
+        let mut expected = from;
+        for (key,_) in iter {
+            if key.len() != prefix.len() + 8 || key[..prefix.len()] != prefix[..] {
+                break;
+            }
+            let blockno = u64_from_slice(&key[prefix.len()..]);
+            if blockno >= to {
+                break;
+            }
+            missing.extend(expected..blockno);
+            expected = blockno + 1;
         }
-        Ok(())
+        missing.extend(expected..to);
+        Ok(missing)
     }
 
     /// create an iterator on internal transactions
@@ -286,16 +297,14 @@ impl AppDB {
 
     /// get number of address links
     pub fn count_addr_tx_links(&self, addr: &Address) -> Result<u64> {
-        let mut key: Vec<u8> = vec![RecordType::TxLinkCount as u8];
-        key.extend_from_slice(addr);
-        Ok(self.get_u64(&key)?.unwrap_or(0))
+        Ok(self.get_u64(&Self::addr_tx_links_count_key(addr))?.unwrap_or(0))
     }
 
-    /// increment the number of address links
-    fn inc_addr_tx_links(&self, addr: &Address) -> Result<u64> {
+    /// key of the number of address links
+    fn addr_tx_links_count_key(addr: &Address) -> Vec<u8> {
         let mut key: Vec<u8> = vec![RecordType::TxLinkCount as u8];
         key.extend_from_slice(addr);
-        self.inc_u64(&key)
+        key
     }
 
     /// set the address contract
@@ -328,11 +337,323 @@ impl AppDB {
         self.set_u64(&[RecordType::NextBlock as u8],n)
     }
 
-    /// increment an u64 counter
-    fn inc_u64(&self, key : &[u8]) -> Result<u64> {
-         let value = 1+self.get_u64(&key)?.unwrap_or(0);
-         self.set_u64(&key,value)?;
-         Ok(value)       
+    /// get the first scanned block, blocks below it are scanned backwards
+    pub fn get_first_scanned_block(&self) -> Result<Option<u64>> {
+        self.get_u64(&[RecordType::FirstBlock as u8])
+    }
+
+    /// set the first scanned block
+    pub fn set_first_scanned_block(&self, n: u64) -> Result<()> {
+        self.set_u64(&[RecordType::FirstBlock as u8],n)
+    }
+
+    /// get the network id of the indexed chain
+    pub fn get_network_id(&self) -> Result<Option<String>> {
+        Ok(self
+            .db
+            .get(&[RecordType::NetworkId as u8])?
+            .map(|bytes| String::from_utf8_lossy(&*bytes).to_string()))
+    }
+
+    /// set the network id of the indexed chain
+    pub fn set_network_id(&self, network_id: &str) -> Result<()> {
+        self.db.put(&[RecordType::NetworkId as u8], network_id.as_bytes())?;
+        Ok(())
+    }
+
+    /// key of the clique signers snapshot after a block
+    fn clique_snapshot_key(blockno: u64) -> Vec<u8> {
+        let mut key = vec![RecordType::CliqueSnapshot as u8];
+        key.extend_from_slice(&u64_to_le(std::u64::MAX - blockno));
+        key
+    }
+
+    /// get the clique signers snapshot after the last scanned block
+    pub fn get_clique_snapshot(&self) -> Result<Option<Snapshot>> {
+        self.get_clique_snapshot_at(std::u64::MAX)
+    }
+
+    /// get the clique signers snapshot after the block, or the last one
+    ///   before it if it is not stored
+    pub fn get_clique_snapshot_at(&self, blockno: u64) -> Result<Option<Snapshot>> {
+        let key = Self::clique_snapshot_key(blockno);
+        let mut iter = self
+            .db
+            .iterator(IteratorMode::From(&key, Direction::Forward));
+
+        match iter.next() {
+            Some((key,value)) if key.len() == 9 && key[0] == RecordType::CliqueSnapshot as u8 =>
+                Ok(Some(from_slice::<Snapshot>(&value)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// get the first block whose clique seal is tracked, the blocks below it
+    ///   are scanned backwards without tracking the signers
+    pub fn get_clique_first_block(&self) -> Result<Option<u64>> {
+        self.get_u64(&[RecordType::CliqueFirstBlock as u8])
+    }
+
+    /// get the history of clique signers changes, from the last one
+    pub fn get_signer_changes(&self) -> Result<Vec<(u64,SignerChange)>> {
+        let prefix = vec![RecordType::CliqueSignerChange as u8];
+        let iter = self
+            .db
+            .iterator(IteratorMode::From(&prefix, Direction::Forward));
+
+        let mut changes = Vec::new();
+        for (key,value) in iter {
+            if key.len() <= prefix.len() || key[..prefix.len()] != prefix[..] {
+                break;
+            }
+            let blockno = std::u64::MAX - u64_from_slice(&key[prefix.len()..prefix.len()+8]);
+            changes.push((blockno, from_slice::<SignerChange>(&value)?));
+        }
+        Ok(changes)
+    }
+
+    /// key of a change in the clique signers
+    fn signer_change_key(blockno: u64, addr: &Address) -> Vec<u8> {
+        let mut key = vec![RecordType::CliqueSignerChange as u8];
+        key.extend_from_slice(&u64_to_le(std::u64::MAX - blockno));
+        key.extend_from_slice(addr);
+        key
+    }
+
+    /// key of the seal of a clique block
+    fn seal_key(blockno: u64) -> Vec<u8> {
+        let mut key = vec![RecordType::CliqueSeal as u8];
+        key.extend_from_slice(&u64_to_le(std::u64::MAX - blockno));
+        key
+    }
+
+    /// key of a block sealed by a clique signer
+    fn sealer_block_key(signer: &Address, blockno: u64) -> Vec<u8> {
+        let mut key = vec![RecordType::SealerBlock as u8];
+        key.extend_from_slice(signer);
+        key.extend_from_slice(&u64_to_le(std::u64::MAX - blockno));
+        key
+    }
+
+    /// key of a clique sealer counter
+    fn sealer_count_key(signer: &Address, counter: SealerCounter) -> Vec<u8> {
+        let mut key = vec![RecordType::SealerCount as u8];
+        key.extend_from_slice(signer);
+        key.push(counter as u8);
+        key
+    }
+
+    /// key of the blocks sealed by a clique signer in a day
+    fn sealer_day_key(signer: &Address, day: u64) -> Vec<u8> {
+        let mut key = vec![RecordType::SealerDay as u8];
+        key.extend_from_slice(signer);
+        key.extend_from_slice(&u64_to_le(day));
+        key
+    }
+
+    /// get the seal of a clique block
+    pub fn get_seal(&self, blockno: u64) -> Result<Option<Seal>> {
+        match self.db.get(&Self::seal_key(blockno))? {
+            None => Ok(None),
+            Some(v) => Ok(Some(from_slice::<Seal>(&v)?))
+        }
+    }
+
+    /// get the addresses that sealed or missed clique blocks
+    #[allow(deprecated)]
+    pub fn get_sealers(&self) -> Result<Vec<Address>> {
+        let prefix = vec![RecordType::SealerCount as u8];
+        let iter = self
+            .db
+            .iterator(IteratorMode::From(&prefix, Direction::Forward));
+
+        let mut sealers : Vec<Address> = Vec::new();
+        for (key,_) in iter {
+            if key.len() != prefix.len() + 21 || key[..prefix.len()] != prefix[..] {
+                break;
+            }
+            let sealer = Address::from_slice(&key[prefix.len()..prefix.len()+20]);
+            if sealers.last() != Some(&sealer) {
+                sealers.push(sealer);
+            }
+        }
+        Ok(sealers)
+    }
+
+    /// get the sealing statistics of a clique signer
+    pub fn get_sealer_stats(&self, signer: &Address) -> Result<SealerStats> {
+        let count = |counter| -> Result<u64> {
+            Ok(self.get_u64(&Self::sealer_count_key(signer, counter))?.unwrap_or(0))
+        };
+
+        let prefix = Self::sealer_block_key(signer, std::u64::MAX);
+        let last_block = self.db
+            .iterator(IteratorMode::From(&prefix, Direction::Forward))
+            .next()
+            .filter(|(key,_)| key.len() == prefix.len() && key[..21] == prefix[..21])
+            .map(|(key,_)| std::u64::MAX - u64_from_slice(&key[21..]));
+
+        Ok(SealerStats {
+            sealed : count(SealerCounter::Sealed)?,
+            in_turn : count(SealerCounter::InTurn)?,
+            out_of_turn : count(SealerCounter::OutOfTurn)?,
+            missed : count(SealerCounter::Missed)?,
+            last_block,
+        })
+    }
+
+    /// get the number of blocks sealed by a clique signer in each day of [from,to)
+    pub fn get_sealer_days(&self, signer: &Address, from: u64, to: u64) -> Result<Vec<u64>> {
+        (from..to)
+            .map(|day| Ok(self.get_u64(&Self::sealer_day_key(signer, day))?.unwrap_or(0)))
+            .collect()
+    }
+
+    /// key of the seal violation of a clique block
+    fn seal_violation_key(blockno: u64) -> Vec<u8> {
+        let mut key = vec![RecordType::SealViolation as u8];
+        key.extend_from_slice(&u64_to_le(std::u64::MAX - blockno));
+        key
+    }
+
+    /// get the seal violation of a clique block
+    pub fn get_seal_violation(&self, blockno: u64) -> Result<Option<SealViolation>> {
+        match self.db.get(&Self::seal_violation_key(blockno))? {
+            None => Ok(None),
+            Some(v) => Ok(Some(from_slice::<SealViolation>(&v)?))
+        }
+    }
+
+    /// get the last seal violations, from the last one
+    pub fn get_seal_violations(&self, limit: usize) -> Result<Vec<(u64,SealViolation)>> {
+        let prefix = vec![RecordType::SealViolation as u8];
+        let iter = self
+            .db
+            .iterator(IteratorMode::From(&prefix, Direction::Forward));
+
+        let mut violations = Vec::new();
+        for (key,value) in iter.take(limit) {
+            if key.len() != prefix.len() + 8 || key[..prefix.len()] != prefix[..] {
+                break;
+            }
+            let blockno = std::u64::MAX - u64_from_slice(&key[prefix.len()..]);
+            violations.push((blockno, from_slice::<SealViolation>(&value)?));
+        }
+        Ok(violations)
+    }
+
+    /// key of the post-merge fields of a block
+    fn block_ext_key(blockno: u64) -> Vec<u8> {
+        let mut key = vec![RecordType::BlockExt as u8];
+        key.extend_from_slice(&u64_to_le(blockno));
+        key
+    }
+
+    /// get the post-merge fields of a block
+    pub fn get_block_ext(&self, blockno: u64) -> Result<Option<BlockExt>> {
+        match self.db.get(&Self::block_ext_key(blockno))? {
+            None => Ok(None),
+            Some(v) => Ok(Some(from_slice::<BlockExt>(&v)?))
+        }
+    }
+
+    /// key of an uncle of a block
+    fn uncle_key(blockno: u64, index: u64) -> Vec<u8> {
+        let mut key = vec![RecordType::Uncle as u8];
+        key.extend_from_slice(&u64_to_le(blockno));
+        key.extend_from_slice(&u64_to_le(index));
+        key
+    }
+
+    /// get the uncles of a block
+    pub fn get_uncles(&self, blockno: u64) -> Result<Vec<Block<H256>>> {
+        let mut prefix = vec![RecordType::Uncle as u8];
+        prefix.extend_from_slice(&u64_to_le(blockno));
+        let iter = self
+            .db
+            .iterator(IteratorMode::From(&prefix, Direction::Forward));
+
+        let mut uncles = Vec::new();
+        for (key,value) in iter {
+            if key.len() != prefix.len() + 8 || key[..prefix.len()] != prefix[..] {
+                break;
+            }
+            uncles.push(from_slice::<Block<H256>>(&value)?);
+        }
+        Ok(uncles)
+    }
+
+    /// key of an address event
+    fn addr_event_key(addr: &Address, event: &AddrEvent) -> Vec<u8> {
+        let mut key = vec![RecordType::AddrEvent as u8];
+        key.extend_from_slice(addr);
+        key.extend_from_slice(&u64_to_le(std::u64::MAX - event.blockno));
+        key.push(event.kind as u8);
+        key.extend_from_slice(&u64_to_le(event.index));
+        key
+    }
+
+    /// key of the index of the address events of a block
+    fn block_addr_event_key(addr: &Address, event: &AddrEvent) -> Vec<u8> {
+        let mut key = vec![RecordType::BlockAddrEvent as u8];
+        key.extend_from_slice(&u64_to_le(event.blockno));
+        key.extend_from_slice(addr);
+        key.push(event.kind as u8);
+        key.extend_from_slice(&u64_to_le(event.index));
+        key
+    }
+
+    /// key of the number of events of an address
+    fn addr_events_count_key(addr: &Address) -> Vec<u8> {
+        let mut key = vec![RecordType::AddrEventCount as u8];
+        key.extend_from_slice(addr);
+        key
+    }
+
+    /// create an iterator on the events of an address, from the last one
+    pub fn iter_addr_events(&self, addr: &Address) -> AddrEvents {
+        let mut key = vec![RecordType::AddrEvent as u8];
+        key.extend_from_slice(addr);
+        let iter = self
+            .db
+            .iterator(IteratorMode::From(&key, Direction::Forward));
+
+        AddrEvents::new(iter, key)
+    }
+
+    /// number of events of an address
+    pub fn count_addr_events(&self, addr: &Address) -> Result<u64> {
+        Ok(self.get_u64(&Self::addr_events_count_key(addr))?.unwrap_or(0))
+    }
+
+    /// key of a genesis allocation
+    fn genesis_alloc_key(addr: &Address) -> Vec<u8> {
+        let mut key = vec![RecordType::GenesisAlloc as u8];
+        key.extend_from_slice(addr);
+        key
+    }
+
+    /// get the genesis allocation of an address
+    pub fn get_genesis_alloc(&self, addr: &Address) -> Result<Option<GenesisAlloc>> {
+        match self.db.get(&Self::genesis_alloc_key(addr))? {
+            None => Ok(None),
+            Some(v) => Ok(Some(from_slice::<GenesisAlloc>(&v)?))
+        }
+    }
+
+    /// create an iterator on the genesis allocations, sorted by address
+    pub fn iter_genesis_allocs(&self) -> GenesisAllocs {
+        let key = vec![RecordType::GenesisAlloc as u8];
+        let iter = self
+            .db
+            .iterator(IteratorMode::From(&key, Direction::Forward));
+
+        GenesisAllocs::new(iter, key)
+    }
+
+    /// number of genesis allocations
+    pub fn count_genesis_allocs(&self) -> Result<u64> {
+        Ok(self.get_u64(&[RecordType::GenesisAllocCount as u8])?.unwrap_or(0))
     }
 
     /// get an u64 counter
@@ -364,3 +685,462 @@ impl AppDB {
     }
 }
 
+/// Stages all the records of a block, including its counters, so they
+///   are written atomically when committed. The counters are read when
+///   committing, so only one writer can exist at a time
+pub struct BlockWriter<'a> {
+    _lock : MutexGuard<'a,()>,
+    appdb : &'a AppDB,
+    batch : WriteBatch,
+    counters : HashMap<Vec<u8>,i64>,
+    staged : HashMap<Vec<u8>,Option<Vec<u8>>>,
+}
+
+impl<'a> BlockWriter<'a> {
+
+    /// add an address that has some relationship with a tx
+    fn add_addrtx_link(&mut self, addr: &Address, tx: &Transaction, inttxno : u64) -> Result<()> {        
+
+        // add the link, incrementing the number of links for this address
+        let key = AppDB::addrtx_link_key(addr, tx, inttxno);
+        if !self.appdb.opt.store_tx {
+            let blockno = tx.block_number.unwrap().low_u64();
+            self.put(&AppDB::block_txlink_key(blockno, &key), &[])?;
+        }
+        self.put_counted(&key, &[], &AppDB::addr_tx_links_count_key(&addr))
+    }
+
+    /// remove the relationship between an address and a tx
+    fn remove_addrtx_link(&mut self, addr: &Address, tx: &Transaction, inttxno : u64) -> Result<()> {        
+
+        // remove the link, decrementing the number of links for this address
+        let key = AppDB::addrtx_link_key(addr, tx, inttxno);
+        self.delete(&AppDB::block_txlink_key(tx.block_number.unwrap().low_u64(), &key))?;
+        self.delete_counted(&key, &AppDB::addr_tx_links_count_key(&addr))
+    }
+
+    /// add all links from a transaction to an address
+    fn add_addrtx_links(&mut self, tx: &Transaction, from: Address, to:Option<Address>, contract:Option<Address>, int_tx_no : u64 )  -> Result<()> {
+        for addr in AppDB::addrtx_links_addrs(from, to, contract) {
+            self.add_addrtx_link(&addr,&tx,int_tx_no)?;
+        }
+        Ok(())
+    }
+
+    /// remove all links from a transaction to an address
+    fn remove_addrtx_links(&mut self, tx: &Transaction, from: Address, to:Option<Address>, contract:Option<Address>, int_tx_no : u64 )  -> Result<()> {
+        for addr in AppDB::addrtx_links_addrs(from, to, contract) {
+            self.remove_addrtx_link(&addr,&tx,int_tx_no)?;
+        }
+        Ok(())
+    }
+
+    /// add an internal transaction
+    fn add_itx(&mut self, tx: &Transaction, itx: &InternalTx, itx_no: u64) -> Result<()> {
+
+        // store the internal transaction 
+        let itx_k = AppDB::itx_key(&tx.hash, itx_no);
+        self.put(&itx_k, &to_vec(itx)?)?;
+
+        // store its addresslinks
+        self.add_addrtx_links(&tx,itx.from, itx.to, itx.contract,itx_no)?;
+
+        Ok(())
+    }
+
+    /// remove an internal transaction
+    fn remove_itx(&mut self, tx: &Transaction, itx: &InternalTx, itx_no: u64) -> Result<()> {
+        self.delete(&AppDB::itx_key(&tx.hash, itx_no))?;
+        self.remove_addrtx_links(&tx,itx.from, itx.to, itx.contract,itx_no)?;
+        Ok(())
+    }
+
+    /// add a transaction, its receipt, internal transactions and address links
+    pub fn add_tx(&mut self, tx: &Transaction, tr: &TransactionReceipt, itxs : Option<&[InternalTx]>) -> Result<()> {
+
+        // check preconditon
+        if tx.to.is_none() && tr.contract_address.is_none() {
+            unreachable!("broken add_tx precondition")
+        }        
+
+        // only store tx if config flag is set
+        if self.appdb.opt.store_tx {
+
+            // store tx
+            let mut tx_k = vec![RecordType::Tx as u8];
+            tx_k.extend_from_slice(&tx.hash);
+            self.put(&tx_k, &to_vec(tx)?)?;
+
+            // store receipt
+            let mut r_k = vec![RecordType::Receipt as u8];
+            r_k.extend_from_slice(&tx.hash);
+            self.put(&r_k, &to_vec(tr)?)?;
+
+            // store internal transactions
+            if self.appdb.opt.store_itx {
+                if let Some(itxs) = itxs {
+                    for (i, itx) in itxs.into_iter().enumerate() {
+                        self.add_itx(&tx,itx,i as u64 + 1)?;
+                    }
+                }
+            }
+        }
+
+        // only store address links if config flag is set
+        if self.appdb.opt.store_addr {
+            // TxLinks
+            self.add_addrtx_links(&tx,tx.from,tx.to,tr.contract_address,0)?;
+        }
+
+        Ok(())
+    }
+
+    /// remove a transaction and its links, reverting add_tx. Records are removed
+    ///   regardless of the options, since they could be stored with other options
+    pub fn remove_tx(&mut self, tx: &Transaction, contract_address: Option<Address>, itxs : Option<&[InternalTx]>) -> Result<()> {
+
+        let mut tx_k = vec![RecordType::Tx as u8];
+        tx_k.extend_from_slice(&tx.hash);
+        self.delete(&tx_k)?;
+
+        let mut r_k = vec![RecordType::Receipt as u8];
+        r_k.extend_from_slice(&tx.hash);
+        self.delete(&r_k)?;
+
+        self.delete(&AppDB::tx_ext_key(&tx.hash))?;
+
+        if let Some(itxs) = itxs {
+            for (i, itx) in itxs.iter().enumerate() {
+                self.remove_itx(&tx,itx,i as u64 + 1)?;
+            }
+        }
+
+        self.remove_addrtx_links(&tx,tx.from,tx.to,contract_address,0)?;
+
+        Ok(())
+    }
+
+    /// add a new block
+    pub fn add_block(&mut self, block: &Block<H256>) -> Result<()> {
+        
+        let blockno = block.number.unwrap().low_u64();
+
+        // the block hash is always stored to detect chain reorganizations
+        self.put(&AppDB::block_hash_key(blockno), &block.hash.unwrap())?;
+
+        if self.appdb.opt.store_tx {
+            // add the block
+            let mut b_k = vec![RecordType::Block as u8];
+            b_k.extend_from_slice(&u64_to_le(blockno));
+
+            self.put(&b_k, &to_vec(block)?)?;
+        } else if self.appdb.opt.store_addr {
+            // without the block, the address links are indexed to remove them
+            self.put(&AppDB::block_txlink_key(blockno, &[]), &[])?;
+        }
+
+        if self.appdb.opt.store_neb && !block.transactions.is_empty() {
+            // annotate a non-empty-block
+            let mut neb_k = vec![RecordType::NonEmptyBlock as u8];
+            let block_no_rev = u64_to_le(std::u64::MAX - blockno);
+            neb_k.extend_from_slice(&block_no_rev);
+
+            // increment counter of non-empty-blocks
+            self.put_counted(&neb_k, &[], &[RecordType::NonEmptyBlockCount as u8])?;
+        }
+        Ok(())
+    }
+
+    /// remove a block, reverting add_block
+    pub fn remove_block(&mut self, blockno: u64) -> Result<()> {
+
+        self.delete(&AppDB::block_hash_key(blockno))?;
+
+        let mut b_k = vec![RecordType::Block as u8];
+        b_k.extend_from_slice(&u64_to_le(blockno));
+        self.delete(&b_k)?;
+
+        let mut neb_k = vec![RecordType::NonEmptyBlock as u8];
+        let block_no_rev = u64_to_le(std::u64::MAX - blockno);
+        neb_k.extend_from_slice(&block_no_rev);
+        self.delete_counted(&neb_k, &[RecordType::NonEmptyBlockCount as u8])?;
+
+        // post-merge fields, uncles and address events
+        self.delete(&AppDB::block_ext_key(blockno))?;
+        for index in 0..self.appdb.get_uncles(blockno)?.len() {
+            self.delete(&AppDB::uncle_key(blockno, index as u64))?;
+        }
+        self.remove_addr_events(blockno)?;
+        self.remove_block_txlinks(blockno)?;
+
+        Ok(())
+    }
+
+    /// remove the clique records of a block, reverting track_block and its
+    ///   snapshot, so the signers are rewound to the previous block. They are
+    ///   not removed by remove_block since the signers are only tracked when
+    ///   scanning forward, so they are only removed when the block is orphaned
+    pub fn remove_clique_block(&mut self, blockno: u64) -> Result<()> {
+
+        // clique signers changes made in this block
+        let mut change_k = vec![RecordType::CliqueSignerChange as u8];
+        change_k.extend_from_slice(&u64_to_le(std::u64::MAX - blockno));
+        let keys : Vec<_> = self.appdb.db
+            .iterator(IteratorMode::From(&change_k, Direction::Forward))
+            .map(|(key,_)| key)
+            .take_while(|key| key.len() > change_k.len() && key[..change_k.len()] == change_k[..])
+            .collect();
+        for key in keys {
+            self.delete(&key)?;
+        }
+
+        self.remove_seal(blockno)?;
+        self.delete(&AppDB::seal_violation_key(blockno))?;
+        self.delete(&AppDB::clique_snapshot_key(blockno))?;
+
+        Ok(())
+    }
+
+    /// set the clique signers snapshot after its block
+    pub fn set_clique_snapshot(&mut self, snapshot: &Snapshot) -> Result<()> {
+        self.put(&AppDB::clique_snapshot_key(snapshot.number), &to_vec(snapshot)?)
+    }
+
+    /// set the first block whose clique seal is tracked
+    pub fn set_clique_first_block(&mut self, n: u64) -> Result<()> {
+        self.put(&[RecordType::CliqueFirstBlock as u8], &u64_to_le(n))
+    }
+
+    /// add a change in the clique signers
+    pub fn add_signer_change(&mut self, blockno: u64, change: &SignerChange) -> Result<()> {
+        self.put(&AppDB::signer_change_key(blockno, &change.signer), &to_vec(change)?)
+    }
+
+    /// add the seal of a clique block, updating the sealer counters
+    pub fn add_seal(&mut self, blockno: u64, seal: &Seal) -> Result<()> {
+        let key = AppDB::seal_key(blockno);
+        if self.exists(&key)? {
+            return Ok(());
+        }
+        let value = to_vec(seal)?;
+        self.put(&key, &value)?;
+        self.staged.insert(key, Some(value));
+        self.put(&AppDB::sealer_block_key(&seal.signer, blockno), &[])?;
+        for key in Self::seal_counters(seal) {
+            self.inc(&key);
+        }
+        Ok(())
+    }
+
+    /// remove the seal of a clique block, updating the sealer counters
+    fn remove_seal(&mut self, blockno: u64) -> Result<()> {
+        let key = AppDB::seal_key(blockno);
+        let seal = match self.get(&key)? {
+            Some(bytes) => from_slice::<Seal>(&bytes)?,
+            None => return Ok(()),
+        };
+        self.delete(&key)?;
+        self.staged.insert(key, None);
+        self.delete(&AppDB::sealer_block_key(&seal.signer, blockno))?;
+        for key in Self::seal_counters(&seal) {
+            self.dec(&key);
+        }
+        Ok(())
+    }
+
+    /// get the counters affected by a seal
+    fn seal_counters(seal: &Seal) -> Vec<Vec<u8>> {
+        let turn = if seal.in_turn { SealerCounter::InTurn } else { SealerCounter::OutOfTurn };
+        let mut counters = vec![
+            AppDB::sealer_count_key(&seal.signer, SealerCounter::Sealed),
+            AppDB::sealer_count_key(&seal.signer, turn),
+            AppDB::sealer_day_key(&seal.signer, seal.day),
+        ];
+        if let Some(missed) = seal.missed {
+            counters.push(AppDB::sealer_count_key(&missed, SealerCounter::Missed));
+        }
+        counters
+    }
+
+    /// flag the seal of a clique block as a violation of the clique rules
+    pub fn add_seal_violation(&mut self, blockno: u64, violation: &SealViolation) -> Result<()> {
+        self.put(&AppDB::seal_violation_key(blockno), &to_vec(violation)?)
+    }
+
+    /// add the typed fields of a transaction
+    pub fn add_tx_ext(&mut self, txhash: &H256, ext: &TxExt) -> Result<()> {
+        if self.appdb.opt.store_tx {
+            self.put(&AppDB::tx_ext_key(txhash), &to_vec(ext)?)?;
+        }
+        Ok(())
+    }
+
+    /// add an uncle of a block
+    pub fn add_uncle(&mut self, blockno: u64, index: u64, uncle: &Block<H256>) -> Result<()> {
+        self.put(&AppDB::uncle_key(blockno, index), &to_vec(uncle)?)
+    }
+
+    /// add the post-merge fields of a block
+    pub fn add_block_ext(&mut self, blockno: u64, ext: &BlockExt) -> Result<()> {
+        self.put(&AppDB::block_ext_key(blockno), &to_vec(ext)?)
+    }
+
+    /// add an address activity that is not a transaction
+    pub fn add_addr_event(&mut self, addr: &Address, event: &AddrEvent) -> Result<()> {
+        if self.appdb.opt.store_addr {
+            self.put_addr_event(addr, event)?;
+            self.put(&AppDB::block_addr_event_key(addr, event), &[])?;
+        }
+        Ok(())
+    }
+
+    /// add an address activity, without indexing it by block
+    fn put_addr_event(&mut self, addr: &Address, event: &AddrEvent) -> Result<()> {
+        let key = AppDB::addr_event_key(addr, event);
+        self.put_counted(&key, &to_vec(event)?, &AppDB::addr_events_count_key(addr))
+    }
+
+    /// remove the address events of a block
+    #[allow(deprecated)]
+    fn remove_addr_events(&mut self, blockno: u64) -> Result<()> {
+        let mut prefix = vec![RecordType::BlockAddrEvent as u8];
+        prefix.extend_from_slice(&u64_to_le(blockno));
+        let keys : Vec<_> = self.appdb.db
+            .iterator(IteratorMode::From(&prefix, Direction::Forward))
+            .map(|(key,_)| key)
+            .take_while(|key| key.len() > prefix.len() && key[..prefix.len()] == prefix[..])
+            .collect();
+
+        for key in keys {
+            // <blockno> <addr> <kind> <index>
+            let addr = Address::from_slice(&key[prefix.len()..prefix.len()+20]);
+            let mut event_k = vec![RecordType::AddrEvent as u8];
+            event_k.extend_from_slice(&addr);
+            event_k.extend_from_slice(&u64_to_le(std::u64::MAX - blockno));
+            event_k.extend_from_slice(&key[prefix.len()+20..]);
+            self.delete_counted(&event_k, &AppDB::addr_events_count_key(&addr))?;
+            self.delete(&key)?;
+        }
+        Ok(())
+    }
+
+    /// remove the address links of a block that are indexed because its
+    ///   transactions are not stored
+    #[allow(deprecated)]
+    fn remove_block_txlinks(&mut self, blockno: u64) -> Result<()> {
+        let prefix = AppDB::block_txlink_key(blockno, &[]);
+        let keys : Vec<_> = self.appdb.db
+            .iterator(IteratorMode::From(&prefix, Direction::Forward))
+            .map(|(key,_)| key)
+            .take_while(|key| key.starts_with(&prefix))
+            .filter(|key| key.len() > prefix.len())
+            .collect();
+
+        for key in keys {
+            // <addrlink-key> is <type> <addr> ...
+            let link_k = &key[prefix.len()..];
+            let addr = Address::from_slice(&link_k[1..21]);
+            self.delete_counted(link_k, &AppDB::addr_tx_links_count_key(&addr))?;
+            self.delete(&key)?;
+        }
+        self.delete(&prefix)
+    }
+
+    /// add an account of the genesis block, with its balance as a block 0
+    ///   address event. It is not indexed by block, so it is not removed
+    ///   when block 0 is reindexed, since it is only imported once
+    pub fn add_genesis_alloc(&mut self, addr: &Address, alloc: &GenesisAlloc) -> Result<()> {
+        let key = AppDB::genesis_alloc_key(addr);
+        self.put_counted(&key, &to_vec(alloc)?, &[RecordType::GenesisAllocCount as u8])?;
+        if self.appdb.opt.store_addr {
+            self.put_addr_event(addr, &AddrEvent {
+                kind : AddrEventKind::Genesis,
+                blockno : 0,
+                index : 0,
+                value : alloc.balance,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// set the next block to scan
+    pub fn set_next_block_to_scan(&mut self, n: u64) -> Result<()> {
+        self.put(&[RecordType::NextBlock as u8], &u64_to_le(n))
+    }
+
+    /// set the first scanned block
+    pub fn set_first_scanned_block(&mut self, n: u64) -> Result<()> {
+        self.put(&[RecordType::FirstBlock as u8], &u64_to_le(n))
+    }
+
+    /// write all the staged records
+    pub fn commit(self) -> Result<()> {
+        let BlockWriter { _lock, appdb, mut batch, counters, .. } = self;
+        for (key, delta) in counters {
+            let value = appdb.get_u64(&key)?.unwrap_or(0) as i64 + delta;
+            batch.put(&key, &u64_to_le(value.max(0) as u64))?;
+        }
+        appdb.db.write(batch)?;
+        Ok(())
+    }
+
+    /// stage a put
+    fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+        self.batch.put(key, value)?;
+        Ok(())
+    }
+
+    /// stage a delete
+    fn delete(&mut self, key: &[u8]) -> Result<()> {
+        self.batch.delete(key)?;
+        Ok(())
+    }
+
+    /// get a record, taking into account the staged changes
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if let Some(value) = self.staged.get(key) {
+            Ok(value.clone())
+        } else {
+            Ok(self.appdb.db.get(key)?.map(|bytes| bytes.to_vec()))
+        }
+    }
+
+    /// check if a record exists, taking into account the staged changes
+    fn exists(&self, key: &[u8]) -> Result<bool> {
+        if let Some(value) = self.staged.get(key) {
+            Ok(value.is_some())
+        } else {
+            Ok(self.appdb.db.get(key)?.is_some())
+        }
+    }
+
+    /// stage the put of a record, incrementing the counter if it is new
+    fn put_counted(&mut self, key: &[u8], value: &[u8], counter: &[u8]) -> Result<()> {
+        if !self.exists(key)? {
+            self.inc(counter);
+        }
+        self.put(key, value)?;
+        self.staged.insert(key.to_vec(), Some(value.to_vec()));
+        Ok(())
+    }
+
+    /// stage the delete of a record, decrementing the counter if it exists
+    fn delete_counted(&mut self, key: &[u8], counter: &[u8]) -> Result<()> {
+        if self.exists(key)? {
+            self.dec(counter);
+            self.delete(key)?;
+            self.staged.insert(key.to_vec(), None);
+        }
+        Ok(())
+    }
+
+    /// stage the increment of an u64 counter
+    fn inc(&mut self, key : &[u8]) {
+        *self.counters.entry(key.to_vec()).or_insert(0) += 1;
+    }
+
+    /// stage the decrement of an u64 counter
+    fn dec(&mut self, key : &[u8]) {
+        *self.counters.entry(key.to_vec()).or_insert(0) -= 1;
+    }
+}
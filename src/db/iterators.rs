@@ -1,9 +1,10 @@
 use rocksdb::{DBIterator};
-use web3::types::{H256};
+use web3::types::{Address, H256};
 use serde_cbor::{from_slice};
 
 use super::utils::*;
-use super::super::eth::types::InternalTx;
+use super::types::AddrEvent;
+use super::super::eth::types::{GenesisAlloc, InternalTx};
 
 impl AddrTxLinks {
     pub fn new(iter: DBIterator, key: Vec<u8>) -> Self {
@@ -83,3 +84,53 @@ impl<'a> Iterator for InternalTxs {
         None
     }
 }
+
+pub struct AddrEvents {
+    iter: DBIterator,
+    key: Vec<u8>,
+}
+
+impl AddrEvents {
+    pub fn new(iter: DBIterator, key: Vec<u8>) -> Self {
+        AddrEvents { iter, key }
+    }
+}
+
+impl<'a> Iterator for AddrEvents {
+    type Item = AddrEvent;
+
+    fn next(&mut self) -> Option<AddrEvent> {
+        if let Some((k,v)) = self.iter.next() {
+            if k.len() > self.key.len() && k[..self.key.len()] == self.key[..] {
+                return Some(from_slice(&v).unwrap());
+            }
+        }
+        None
+    }
+}
+
+pub struct GenesisAllocs {
+    iter: DBIterator,
+    key: Vec<u8>,
+}
+
+impl GenesisAllocs {
+    pub fn new(iter: DBIterator, key: Vec<u8>) -> Self {
+        GenesisAllocs { iter, key }
+    }
+}
+
+#[allow(deprecated)]
+impl<'a> Iterator for GenesisAllocs {
+    type Item = (Address,GenesisAlloc);
+
+    fn next(&mut self) -> Option<(Address,GenesisAlloc)> {
+        if let Some((k,v)) = self.iter.next() {
+            if k.len() > self.key.len() && k[..self.key.len()] == self.key[..] {
+                let addr = Address::from_slice(&k[self.key.len()..]);
+                return Some((addr,from_slice(&v).unwrap()));
+            }
+        }
+        None
+    }
+}
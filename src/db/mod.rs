@@ -5,6 +5,6 @@ mod types;
 mod utils;
 mod iterators;
 
-pub use self::appdb::{AppDB,Options};
+pub use self::appdb::{AppDB,BlockWriter,Options};
 pub use self::types::*;
 pub use self::error::*;
\ No newline at end of file
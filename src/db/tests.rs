@@ -1,32 +1,36 @@
 #[cfg(test)]
 mod tests {
     use super::super::appdb::*;
+    use super::super::types::{AddrEvent, AddrEventKind, Seal, SealerStats};
+    use super::super::error::Error;
     use super::super::super::eth::types::*;
+    use super::super::super::eth::geth::clique::{SealViolation, SignerChange, Snapshot};
 
-    use rand::distributions::Alphanumeric;
-    use rand::{thread_rng, Rng};
-    use std::iter;
+    use super::super::super::fixtures::{block, temp_db};
+
+    use std::sync::Arc;
+    use std::thread;
     use web3::types::{Bytes, Transaction, Address, H256, TransactionReceipt,U128, U256, H2048};
 
     fn init() -> AppDB {
-        let mut rng = thread_rng();
-        let chars: String = iter::repeat(())
-            .map(|()| rng.sample(Alphanumeric))
-            .take(7)
-            .collect();
-
-        let mut tmpfile = std::env::temp_dir();
-        tmpfile.push(chars);
-
-        AppDB::open_default(
-            tmpfile.as_os_str().to_str().expect("bad OS filename"),
-            Options {
-                store_itx  : true,
-                store_tx   : true,
-                store_addr : true,
-                store_neb  : true,            
-            }
-        ).expect("unable to create db")
+        temp_db(Options {
+            store_itx  : true,
+            store_tx   : true,
+            store_addr : true,
+            store_neb  : true,
+        })
+    }
+
+    fn add_tx(appdb: &AppDB, tx: &Transaction, tr: &TransactionReceipt, itxs : Option<&[InternalTx]>) -> Result<(),Error> {
+        let mut writer = appdb.writer();
+        writer.add_tx(tx, tr, itxs)?;
+        writer.commit()
+    }
+
+    fn remove_tx(appdb: &AppDB, tx: &Transaction, contract_address: Option<Address>, itxs : Option<&[InternalTx]>) -> Result<(),Error> {
+        let mut writer = appdb.writer();
+        writer.remove_tx(tx, contract_address, itxs)?;
+        writer.commit()
     }
 
     struct TestVars {
@@ -158,7 +162,7 @@ mod tests {
 
         // + tx_a1_to_a2
 
-        assert_eq!((), appdb.add_tx(&v.tx_a1_to_a2, &v.rcp_a1_to_a2, Some(&[])).unwrap());
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a2, &v.rcp_a1_to_a2, Some(&[])).unwrap());
         assert_eq!(1, appdb.count_addr_tx_links(&v.a1).unwrap());
         assert_eq!(1, appdb.count_addr_tx_links(&v.a2).unwrap());
 
@@ -172,7 +176,7 @@ mod tests {
 
         // + tx_a1_to_contract
 
-        assert_eq!((), appdb.add_tx(&v.tx_a1_to_contract, &v.rcp_a1_to_contract,Some(&[])).unwrap());
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_contract, &v.rcp_a1_to_contract,Some(&[])).unwrap());
         assert_eq!(2, appdb.count_addr_tx_links(&v.a1).unwrap());
         assert_eq!(1, appdb.count_addr_tx_links(&v.a2).unwrap());
         assert_eq!(1, appdb.count_addr_tx_links(&v.a3).unwrap());
@@ -188,7 +192,7 @@ mod tests {
 
         // + tx_a1_to_a1
 
-        assert_eq!((), appdb.add_tx(&v.tx_a1_to_a1, &v.rcp_a1_to_a1,Some(&[])).unwrap());
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a1, &v.rcp_a1_to_a1,Some(&[])).unwrap());
         assert_eq!(3, appdb.count_addr_tx_links(&v.a1).unwrap());
 
         let mut it_a1 = appdb.iter_addr_tx_links(&v.a1);
@@ -204,8 +208,8 @@ mod tests {
         let appdb = init();
         let v = vars();
 
-        assert_eq!((), appdb.add_tx(&v.tx_a1_to_a2, &v.rcp_a1_to_a2, Some(&[
-            InternalTx { from : v.a2, to: Some(v.a3), contract:None, input: Vec::new(), value:v.one_u256 }
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a2, &v.rcp_a1_to_a2, Some(&[
+            InternalTx { from : v.a2, to: Some(v.a3), contract:None, input: Vec::new(), value:v.one_u256, ..Default::default() }
         ])).unwrap());
 
         let mut it_a2 = appdb.iter_addr_tx_links(&v.a2);
@@ -220,8 +224,8 @@ mod tests {
         let appdb = init();
         let v = vars();
 
-        assert_eq!((), appdb.add_tx(&v.tx_a1_to_a2, &v.rcp_a1_to_a2, Some(&[
-            InternalTx { from : v.a2, to: None, contract:Some(v.a4), input: Vec::new(), value:v.one_u256}
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a2, &v.rcp_a1_to_a2, Some(&[
+            InternalTx { from : v.a2, to: None, contract:Some(v.a4), input: Vec::new(), value:v.one_u256, ..Default::default() }
         ])).unwrap());
 
         let mut it_a2 = appdb.iter_addr_tx_links(&v.a2);
@@ -239,8 +243,8 @@ mod tests {
         let appdb = init();
         let v = vars();
 
-        assert_eq!((), appdb.add_tx(&v.tx_a1_to_a1, &v.rcp_a1_to_a1, Some(&[
-            InternalTx { from : v.a1, to: Some(v.a1), contract:None, input: Vec::new(), value:v.one_u256 }
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a1, &v.rcp_a1_to_a1, Some(&[
+            InternalTx { from : v.a1, to: Some(v.a1), contract:None, input: Vec::new(), value:v.one_u256, ..Default::default() }
         ])).unwrap());
 
         let mut it_a1 = appdb.iter_addr_tx_links(&v.a1);
@@ -254,9 +258,9 @@ mod tests {
         let appdb = init();
         let v = vars();
 
-        assert_eq!((), appdb.add_tx(&v.tx_a1_to_a1, &v.rcp_a1_to_a1, Some(&[
-            InternalTx { from : v.a3, to: Some(v.a1), contract:None, input: Vec::new(), value:v.one_u256 },
-            InternalTx { from : v.a2, to: None, contract:Some(v.a3), input: Vec::new(), value:v.one_u256 }
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a1, &v.rcp_a1_to_a1, Some(&[
+            InternalTx { from : v.a3, to: Some(v.a1), contract:None, input: Vec::new(), value:v.one_u256, ..Default::default() },
+            InternalTx { from : v.a2, to: None, contract:Some(v.a3), input: Vec::new(), value:v.one_u256, ..Default::default() }
         ])).unwrap());
 
         assert_eq!(0,appdb._count_itxs(&v.h1));
@@ -268,6 +272,129 @@ mod tests {
         assert_eq!(None, i_itx.next());
     }
 
+    #[test]
+    fn test_add_and_get_nested_itx() {
+        let appdb = init();
+        let v = vars();
+
+        let call = InternalTx {
+            from : v.a2, to: Some(v.a3), contract:None, input: Vec::new(), value:v.one_u256,
+            call_type: "CALL".to_string(), depth: 1, ..Default::default()
+        };
+        let subcall = InternalTx {
+            from : v.a3, to: Some(v.a4), contract:None, input: Vec::new(), value:v.one_u256,
+            call_type: "STATICCALL".to_string(), depth: 2, parent: 1,
+            error: Some("execution reverted".to_string()), ..Default::default()
+        };
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a2, &v.rcp_a1_to_a2, Some(&[call, subcall])).unwrap());
+
+        let itx = appdb.get_itx(&v.h1, 2).unwrap().unwrap();
+        assert_eq!((2, 1), (itx.depth, itx.parent));
+        assert_eq!("STATICCALL", itx.call_type);
+        assert_eq!(Some("execution reverted".to_string()), itx.error);
+        assert_eq!(1, appdb.count_addr_tx_links(&v.a4).unwrap());
+    }
+
+    #[test]
+    fn test_add_and_remove_failed_nested_create() {
+        let appdb = init();
+        let v = vars();
+
+        // a failed create has no destination nor contract address
+        let itxs = [
+            InternalTx {
+                from : v.a2, to: None, contract:None, input: Vec::new(), value:v.one_u256,
+                call_type: "CREATE".to_string(), depth: 1,
+                error: Some("out of gas".to_string()), ..Default::default()
+            }
+        ];
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a2, &v.rcp_a1_to_a2, Some(&itxs)).unwrap());
+        assert_eq!(1, appdb._count_itxs(&v.h1));
+        assert_eq!(2, appdb.count_addr_tx_links(&v.a2).unwrap());
+
+        let mut it_a2 = appdb.iter_addr_tx_links(&v.a2);
+        assert_eq!(Some((v.h1,1)), it_a2.next());
+        assert_eq!(Some((v.h1,0)), it_a2.next());
+        assert_eq!(None, it_a2.next());
+
+        assert_eq!((), remove_tx(&appdb, &v.tx_a1_to_a2, None, Some(&itxs)).unwrap());
+        assert_eq!(0, appdb._count_itxs(&v.h1));
+        assert_eq!(0, appdb.count_addr_tx_links(&v.a2).unwrap());
+    }
+
+    #[test]
+    fn test_add_and_remove_tx() {
+        let appdb = init();
+        let v = vars();
+
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a2, &v.rcp_a1_to_a2, Some(&[
+            InternalTx { from : v.a2, to: None, contract:Some(v.a4), input: Vec::new(), value:v.one_u256, ..Default::default() }
+        ])).unwrap());
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_contract, &v.rcp_a1_to_contract,Some(&[])).unwrap());
+
+        assert_eq!((), remove_tx(&appdb, &v.tx_a1_to_a2, None, Some(&[
+            InternalTx { from : v.a2, to: None, contract:Some(v.a4), input: Vec::new(), value:v.one_u256, ..Default::default() }
+        ])).unwrap());
+
+        assert_eq!(1, appdb.count_addr_tx_links(&v.a1).unwrap());
+        assert_eq!(0, appdb.count_addr_tx_links(&v.a2).unwrap());
+        assert_eq!(0, appdb.count_addr_tx_links(&v.a4).unwrap());
+        assert_eq!(0, appdb._count_itxs(&v.h1));
+        assert_eq!(Ok(None), appdb.get_tx(&v.h1).map(|tx| tx.map(|tx| tx.hash)));
+
+        let mut it_a1 = appdb.iter_addr_tx_links(&v.a1);
+        assert_eq!(Some((v.h2,0)), it_a1.next());
+        assert_eq!(None, it_a1.next());
+        assert_eq!(None, appdb.iter_addr_tx_links(&v.a2).next());
+    }
+
+    #[test]
+    fn test_add_tx_is_idempotent() {
+        let appdb = init();
+        let v = vars();
+
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a2, &v.rcp_a1_to_a2, None).unwrap());
+        assert_eq!((), add_tx(&appdb, &v.tx_a1_to_a2, &v.rcp_a1_to_a2, None).unwrap());
+        assert_eq!(1, appdb.count_addr_tx_links(&v.a1).unwrap());
+        assert_eq!(1, appdb.count_addr_tx_links(&v.a2).unwrap());
+
+        // remove and add again in the same batch, as when reindexing
+
+        let mut writer = appdb.writer();
+        writer.remove_tx(&v.tx_a1_to_a2, None, None).unwrap();
+        writer.add_tx(&v.tx_a1_to_a2, &v.rcp_a1_to_a2, None).unwrap();
+        writer.commit().unwrap();
+        assert_eq!(1, appdb.count_addr_tx_links(&v.a1).unwrap());
+        assert_eq!(1, appdb.count_addr_tx_links(&v.a2).unwrap());
+
+        assert_eq!((), remove_tx(&appdb, &v.tx_a1_to_a2, None, None).unwrap());
+        assert_eq!((), remove_tx(&appdb, &v.tx_a1_to_a2, None, None).unwrap());
+        assert_eq!(0, appdb.count_addr_tx_links(&v.a1).unwrap());
+        assert_eq!(0, appdb.count_addr_tx_links(&v.a2).unwrap());
+    }
+
+    #[test]
+    fn test_writer_is_atomic() {
+        let appdb = init();
+        let v = vars();
+
+        // nothing is written until commit
+
+        let mut writer = appdb.writer();
+        writer.add_tx(&v.tx_a1_to_a2, &v.rcp_a1_to_a2, None).unwrap();
+        writer.add_tx(&v.tx_a1_to_a1, &v.rcp_a1_to_a1, None).unwrap();
+        writer.set_next_block_to_scan(13).unwrap();
+        assert_eq!(0, appdb.count_addr_tx_links(&v.a1).unwrap());
+        assert_eq!(Ok(None), appdb.get_next_block_to_scan());
+
+        // counters staged in the same batch are accumulated
+
+        writer.commit().unwrap();
+        assert_eq!(2, appdb.count_addr_tx_links(&v.a1).unwrap());
+        assert_eq!(1, appdb.count_addr_tx_links(&v.a2).unwrap());
+        assert_eq!(Ok(Some(13)), appdb.get_next_block_to_scan());
+    }
+
     #[test]
     fn test_set_get_block() {
         let appdb = init();
@@ -276,6 +403,238 @@ mod tests {
         assert_eq!(Ok(Some(1)), appdb.get_next_block_to_scan());
         assert_eq!(Ok(()), appdb.set_next_block_to_scan(0xaabbccdd11223344));
         assert_eq!(Ok(Some(0xaabbccdd11223344)), appdb.get_next_block_to_scan());
+
+        assert_eq!(Ok(None), appdb.get_first_scanned_block());
+        assert_eq!(Ok(()), appdb.set_first_scanned_block(7));
+        assert_eq!(Ok(Some(7)), appdb.get_first_scanned_block());
+        assert_eq!(Ok(Some(0xaabbccdd11223344)), appdb.get_next_block_to_scan());
+
+        assert_eq!(Ok(None), appdb.get_network_id());
+        assert_eq!(Ok(()), appdb.set_network_id("1337"));
+        assert_eq!(Ok(Some("1337".to_string())), appdb.get_network_id());
+    }
+
+    #[test]
+    fn test_missing_blocks() {
+        let appdb = init();
+        let mut writer = appdb.writer();
+        for number in &[1, 2, 4, 7] {
+            writer.add_block(&block(*number)).unwrap();
+        }
+        writer.commit().unwrap();
+
+        assert_eq!(Ok(vec![3, 5, 6]), appdb.missing_blocks(1, 7));
+        assert_eq!(Ok(vec![0, 3]), appdb.missing_blocks(0, 5));
+        assert_eq!(Ok(vec![]), appdb.missing_blocks(1, 3));
+    }
+
+    #[test]
+    fn test_add_and_remove_signer_changes() {
+        let appdb = init();
+        let v = vars();
+        let (a1, a2) = (v.a1, v.a2);
+
+        let snapshot10 = Snapshot { number: 10, signers: vec![a1, a2], votes: Vec::new(), recents: Vec::new() };
+        let snapshot11 = Snapshot { number: 11, signers: vec![a2], votes: Vec::new(), recents: Vec::new() };
+        let mut writer = appdb.writer();
+        writer.add_signer_change(10, &SignerChange { signer: a2, added: true }).unwrap();
+        writer.add_signer_change(11, &SignerChange { signer: a1, added: false }).unwrap();
+        writer.set_clique_snapshot(&snapshot10).unwrap();
+        writer.set_clique_snapshot(&snapshot11).unwrap();
+        writer.commit().unwrap();
+
+        // the signers are kept at every height
+        assert_eq!(Ok(Some(snapshot11.clone())), appdb.get_clique_snapshot());
+        assert_eq!(Ok(Some(snapshot10.clone())), appdb.get_clique_snapshot_at(10));
+        assert_eq!(Ok(Some(snapshot11)), appdb.get_clique_snapshot_at(20));
+        assert_eq!(Ok(None), appdb.get_clique_snapshot_at(9));
+        assert_eq!(Ok(vec![
+            (11, SignerChange { signer: a1, added: false }),
+            (10, SignerChange { signer: a2, added: true }),
+        ]), appdb.get_signer_changes());
+
+        // reindexed blocks keep the signers changes
+        let mut writer = appdb.writer();
+        writer.remove_block(11).unwrap();
+        writer.commit().unwrap();
+        assert_eq!(2, appdb.get_signer_changes().unwrap().len());
+
+        let mut writer = appdb.writer();
+        writer.remove_clique_block(11).unwrap();
+        writer.commit().unwrap();
+
+        // the orphaned block rewinds the signers to the previous block
+        assert_eq!(Ok(Some(snapshot10)), appdb.get_clique_snapshot());
+        assert_eq!(Ok(vec![
+            (10, SignerChange { signer: a2, added: true }),
+        ]), appdb.get_signer_changes());
+    }
+
+    #[test]
+    fn test_add_and_remove_seals() {
+        let appdb = init();
+        let v = vars();
+        let (a1, a2) = (v.a1, v.a2);
+
+        let mut writer = appdb.writer();
+        writer.add_seal(1, &Seal { signer: a1, in_turn: true, missed: None, day: 5 }).unwrap();
+        writer.add_seal(2, &Seal { signer: a1, in_turn: false, missed: Some(a2), day: 6 }).unwrap();
+        writer.add_seal(2, &Seal { signer: a1, in_turn: false, missed: Some(a2), day: 6 }).unwrap();
+        writer.add_seal_violation(2, &SealViolation::RecentlySigned(a1, 1)).unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(Ok(vec![(2, SealViolation::RecentlySigned(a1, 1))]), appdb.get_seal_violations(10));
+
+        let sealers = appdb.get_sealers().unwrap();
+        assert_eq!(2, sealers.len());
+        assert!(sealers.contains(&a1) && sealers.contains(&a2));
+        assert_eq!(Ok(SealerStats { sealed: 2, in_turn: 1, out_of_turn: 1, missed: 0, last_block: Some(2) }),
+            appdb.get_sealer_stats(&a1));
+        assert_eq!(Ok(SealerStats { missed: 1, ..Default::default() }),
+            appdb.get_sealer_stats(&a2));
+        assert_eq!(Ok(vec![0, 1, 1]), appdb.get_sealer_days(&a1, 4, 7));
+
+        let mut writer = appdb.writer();
+        writer.remove_clique_block(2).unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(Ok(None), appdb.get_seal(2));
+        assert_eq!(Ok(None), appdb.get_seal_violation(2));
+        assert_eq!(Ok(SealerStats { sealed: 1, in_turn: 1, out_of_turn: 0, missed: 0, last_block: Some(1) }),
+            appdb.get_sealer_stats(&a1));
+        assert_eq!(Ok(SealerStats::default()), appdb.get_sealer_stats(&a2));
+    }
+
+    #[test]
+    fn test_concurrent_writers_counters() {
+        let appdb = Arc::new(init());
+        let v = vars();
+
+        // each writer adds its own links to the same addresses
+        let handles : Vec<_> = (0..4u64).map(|n| {
+            let appdb = appdb.clone();
+            let (mut tx, rcp) = (v.tx_a1_to_a2.clone(), v.rcp_a1_to_a2.clone());
+            thread::spawn(move || {
+                for i in 0..25u64 {
+                    tx.hash = H256::from(n * 100 + i);
+                    add_tx(&appdb, &tx, &rcp, None).unwrap();
+                }
+            })
+        }).collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(100, appdb.count_addr_tx_links(&v.a1).unwrap());
+        assert_eq!(100, appdb.count_addr_tx_links(&v.a2).unwrap());
+    }
+
+    #[test]
+    fn test_add_and_remove_seal_in_same_writer() {
+        let appdb = init();
+        let v = vars();
+        let (a1, a2) = (v.a1, v.a2);
+
+        let mut writer = appdb.writer();
+        writer.add_seal(1, &Seal { signer: a1, in_turn: false, missed: Some(a2), day: 5 }).unwrap();
+        writer.remove_clique_block(1).unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(Ok(None), appdb.get_seal(1));
+        assert_eq!(Ok(SealerStats::default()), appdb.get_sealer_stats(&a1));
+        assert_eq!(Ok(SealerStats::default()), appdb.get_sealer_stats(&a2));
+    }
+
+    #[test]
+    fn test_add_and_remove_addr_events() {
+        let appdb = init();
+        let v = vars();
+        let withdrawal = |blockno, index| AddrEvent {
+            kind: AddrEventKind::Withdrawal,
+            blockno,
+            index,
+            value: U256::from(1000),
+        };
+
+        let mut writer = appdb.writer();
+        writer.add_addr_event(&v.a1, &withdrawal(1, 0)).unwrap();
+        writer.add_addr_event(&v.a1, &withdrawal(2, 1)).unwrap();
+        writer.add_addr_event(&v.a1, &withdrawal(2, 1)).unwrap();
+        writer.add_addr_event(&v.a2, &withdrawal(2, 2)).unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(Ok(2), appdb.count_addr_events(&v.a1));
+        assert_eq!(vec![withdrawal(2, 1), withdrawal(1, 0)], appdb.iter_addr_events(&v.a1).collect::<Vec<_>>());
+        assert_eq!(vec![withdrawal(2, 2)], appdb.iter_addr_events(&v.a2).collect::<Vec<_>>());
+
+        let mut writer = appdb.writer();
+        writer.remove_block(2).unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(Ok(1), appdb.count_addr_events(&v.a1));
+        assert_eq!(vec![withdrawal(1, 0)], appdb.iter_addr_events(&v.a1).collect::<Vec<_>>());
+        assert_eq!(Ok(0), appdb.count_addr_events(&v.a2));
+    }
+
+    #[test]
+    fn test_add_and_remove_tx_ext() {
+        let appdb = init();
+        let v = vars();
+        let ext = TxExt {
+            tx_type: Some(U256::from(2)),
+            max_fee_per_gas: Some(U256::from(100)),
+            max_priority_fee_per_gas: Some(U256::from(2)),
+            effective_gas_price: Some(U256::from(12)),
+            ..Default::default()
+        };
+
+        let mut writer = appdb.writer();
+        writer.add_tx(&v.tx_a1_to_a2, &v.rcp_a1_to_a2, None).unwrap();
+        writer.add_tx_ext(&v.tx_a1_to_a2.hash, &ext).unwrap();
+        writer.commit().unwrap();
+        assert_eq!(Ok(Some(ext)), appdb.get_tx_ext(&v.tx_a1_to_a2.hash));
+
+        remove_tx(&appdb, &v.tx_a1_to_a2, None, None).unwrap();
+        assert_eq!(Ok(None), appdb.get_tx_ext(&v.tx_a1_to_a2.hash));
+    }
+
+    #[test]
+    fn test_add_genesis_allocs() {
+        let appdb = init();
+        let v = vars();
+        let alloc = GenesisAlloc {
+            balance: U256::from(1000),
+            code: vec![0x60, 0x00],
+            storage: vec![(H256::zero(), H256::zero())],
+            ..Default::default()
+        };
+
+        let mut writer = appdb.writer();
+        writer.add_genesis_alloc(&v.a1, &alloc).unwrap();
+        writer.add_genesis_alloc(&v.a2, &GenesisAlloc::default()).unwrap();
+        writer.commit().unwrap();
+
+        // importing again does not change the counters
+        let mut writer = appdb.writer();
+        writer.add_genesis_alloc(&v.a1, &alloc).unwrap();
+        writer.commit().unwrap();
+
+        assert_eq!(Ok(2), appdb.count_genesis_allocs());
+        assert_eq!(Ok(Some(alloc.clone())), appdb.get_genesis_alloc(&v.a1));
+        assert_eq!(2, appdb.iter_genesis_allocs().count());
+
+        assert_eq!(Ok(1), appdb.count_addr_events(&v.a1));
+        let events : Vec<_> = appdb.iter_addr_events(&v.a1).collect();
+        assert_eq!(AddrEventKind::Genesis, events[0].kind);
+        assert_eq!(0, events[0].blockno);
+        assert_eq!(alloc.balance, events[0].value);
+
+        // reindexing block 0 keeps the genesis events
+        let mut writer = appdb.writer();
+        writer.remove_block(0).unwrap();
+        writer.commit().unwrap();
+        assert_eq!(Ok(1), appdb.count_addr_events(&v.a1));
+        assert_eq!(1, appdb.iter_addr_events(&v.a1).count());
     }
 
 }
@@ -1,3 +1,5 @@
+use web3::types::{Address, U256};
+
 #[derive(Debug,Copy, Clone, PartialEq)]
 #[repr(u8)]
 pub enum RecordType {
@@ -11,6 +13,77 @@ pub enum RecordType {
     NonEmptyBlock = 8,
     NonEmptyBlockCount = 9,
     IntTx = 10,
+    BlockHash = 11,
+    FirstBlock = 12,
+    NetworkId = 13,
+    CliqueSnapshot = 14,
+    CliqueSignerChange = 15,
+    CliqueSeal = 16,
+    SealerBlock = 17,
+    SealerCount = 18,
+    SealerDay = 19,
+    SealViolation = 20,
+    BlockExt = 21,
+    AddrEvent = 22,
+    AddrEventCount = 23,
+    BlockAddrEvent = 24,
+    TxExt = 25,
+    Uncle = 26,
+    GenesisAlloc = 27,
+    GenesisAllocCount = 28,
+    CliqueFirstBlock = 29,
+    BlockTxLink = 30,
+}
+
+/// Kind of an address activity that is not a transaction
+#[derive(Debug,Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum AddrEventKind {
+    Withdrawal = 1,
+    BlockReward = 2,
+    UncleReward = 3,
+    Genesis = 4,
+}
+
+/// An address activity that is not a transaction, like a beacon withdrawal.
+///   index is the position of the event in the block (or the uncle index),
+///   value is in wei
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct AddrEvent {
+    pub kind : AddrEventKind,
+    pub blockno : u64,
+    pub index : u64,
+    pub value : U256,
+}
+
+/// Kind of the clique sealer counters
+#[derive(Debug,Copy, Clone, PartialEq)]
+#[repr(u8)]
+pub enum SealerCounter {
+    Sealed = 1,
+    InTurn = 2,
+    OutOfTurn = 3,
+    Missed = 4,
+}
+
+/// The seal of a clique block, missed is the in-turn signer of an
+///   out-of-turn block
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct Seal {
+    pub signer : Address,
+    pub in_turn : bool,
+    pub missed : Option<Address>,
+    pub day : u64,
+}
+
+/// Sealing statistics of a clique signer
+#[derive(Debug,Default,Clone,PartialEq)]
+pub struct SealerStats {
+    pub sealed : u64,
+    pub in_turn : u64,
+    pub out_of_turn : u64,
+    pub missed : u64,
+    pub last_block : Option<u64>,
 }
 
 #[derive(Debug,Serialize,Deserialize)]
@@ -21,4 +94,11 @@ pub struct Contract {
     pub compiler: String,
     pub optimized: bool,
     pub constructor : Vec<u8>, 
+    #[serde(default)]
+    pub storage_layout : Option<String>,
+    #[serde(default)]
+    pub srcmap : Option<String>,
+    /// index of the source in the file field of the srcmap
+    #[serde(default)]
+    pub srcmap_file : i64,
 }
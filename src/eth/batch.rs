@@ -0,0 +1,209 @@
+use web3::futures::Future;
+use web3::helpers;
+use web3::types::{Block, BlockNumber, Transaction, TransactionReceipt};
+use web3::BatchTransport;
+
+use super::error::{Error, Result};
+use super::failover::is_transient;
+use super::geth::web3::{DbgBlockTrace, DbgInternalTxs};
+use super::parity::trace::{parse_block_traces, TraceEntry};
+use super::types::{InternalTx, TxExt};
+
+/// Retrieves the per-transaction data of a block (receipts, traces) in
+///   the minimum number of json-rpc roundtrips
+pub struct BatchReader {
+    block_receipts : bool,
+    block_traces : bool,
+    trace_api : bool,
+}
+
+impl BatchReader {
+
+    /// create a reader, trace_api selects the trace_ namespace instead of
+    ///   debug_ to retrieve the internal transactions
+    pub fn new(trace_api: bool) -> Self {
+        BatchReader { block_receipts : true, block_traces : true, trace_api }
+    }
+
+    /// retrieve the receipts of all block transactions, with their effective gas
+    ///   price and blob gas, using eth_getBlockReceipts if the node supports it
+    ///   or a batch of eth_getTransactionReceipt if not
+    pub fn receipts<T: BatchTransport>(
+        &mut self,
+        transport: &T,
+        block: &Block<Transaction>
+    ) -> Result<Vec<(TransactionReceipt,TxExt)>> {
+
+        if block.transactions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if self.block_receipts {
+            match self.block_receipts(transport, block) {
+                Ok(Some(receipts)) => return Ok(receipts),
+                Ok(None) => {
+                    warn!("eth_getBlockReceipts returned no receipts, falling back to batched eth_getTransactionReceipt");
+                    self.block_receipts = false;
+                }
+                Err(err) => {
+                    // connection errors do not mean that the method is not supported
+                    if is_transient_error(&err) {
+                        return Err(err);
+                    }
+                    warn!("eth_getBlockReceipts not available ({:?}), falling back to batched eth_getTransactionReceipt", err);
+                    self.block_receipts = false;
+                }
+            }
+        }
+
+        let params = block.transactions.iter()
+            .map(|tx| vec![helpers::serialize(&tx.hash)])
+            .collect();
+
+        let mut receipts = Vec::new();
+        for (tx, value) in block.transactions.iter().zip(batch_call(transport, "eth_getTransactionReceipt", params)?) {
+            if value.is_null() {
+                return Err(Error::ReceiptNotFound(tx.hash));
+            }
+            receipts.push(parse_receipt(value)?);
+        }
+        Ok(receipts)
+    }
+
+    /// retrieve the internal transactions of all block transactions with
+    ///   debug_traceBlockByNumber if the node supports it or a batch of
+    ///   debug_traceTransaction if not, or trace_block in the trace_ api
+    pub fn internal_txs<T: BatchTransport>(
+        &mut self,
+        transport: &T,
+        block: &Block<Transaction>
+    ) -> Result<Vec<Vec<InternalTx>>> {
+
+        if block.transactions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        if self.trace_api {
+            let blockno = BlockNumber::Number(block.number.unwrap().low_u64());
+            let value = transport
+                .execute("trace_block", vec![helpers::serialize(&blockno)])
+                .wait()?;
+            let traces = serde_json::from_value::<Vec<TraceEntry>>(value)?;
+            return Ok(parse_block_traces(&traces, block.transactions.len())?);
+        }
+
+        if self.block_traces {
+            match self.block_traces(transport, block) {
+                Ok(Some(itxs)) => return Ok(itxs),
+                Ok(None) => {
+                    warn!("debug_traceBlockByNumber failed in block {}, falling back to batched debug_traceTransaction",
+                        block.number.unwrap());
+                    self.block_traces = false;
+                }
+                Err(err) => {
+                    if is_transient_error(&err) {
+                        return Err(err);
+                    }
+                    warn!("debug_traceBlockByNumber not available ({:?}), falling back to batched debug_traceTransaction", err);
+                    self.block_traces = false;
+                }
+            }
+        }
+
+        let params = block.transactions.iter()
+            .map(|tx| vec![
+                helpers::serialize(&tx.hash),
+                json!({"tracer":"callTracer"}),
+            ])
+            .collect();
+
+        let mut itxs = Vec::new();
+        for value in batch_call(transport, "debug_traceTransaction", params)? {
+            itxs.push(serde_json::from_value::<DbgInternalTxs>(value)?.parse()?);
+        }
+        Ok(itxs)
+    }
+
+    /// call debug_traceBlockByNumber, returning None if any of the transactions
+    ///   could not be traced
+    fn block_traces<T: BatchTransport>(
+        &self,
+        transport: &T,
+        block: &Block<Transaction>
+    ) -> Result<Option<Vec<Vec<InternalTx>>>> {
+
+        let blockno = BlockNumber::Number(block.number.unwrap().low_u64());
+        let value = transport
+            .execute("debug_traceBlockByNumber", vec![
+                helpers::serialize(&blockno),
+                json!({"tracer":"callTracer"}),
+            ])
+            .wait()?;
+
+        let traces = serde_json::from_value::<Vec<DbgBlockTrace>>(value)?;
+        if traces.len() != block.transactions.len() {
+            return Ok(None);
+        }
+
+        let mut itxs = Vec::new();
+        for trace in traces {
+            match trace.result {
+                Some(result) => itxs.push(result.parse()?),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(itxs))
+    }
+
+    /// call eth_getBlockReceipts, checking that there is one receipt for each transaction
+    fn block_receipts<T: BatchTransport>(
+        &self,
+        transport: &T,
+        block: &Block<Transaction>
+    ) -> Result<Option<Vec<(TransactionReceipt,TxExt)>>> {
+
+        let blockno = BlockNumber::Number(block.number.unwrap().low_u64());
+        let value = transport
+            .execute("eth_getBlockReceipts", vec![helpers::serialize(&blockno)])
+            .wait()?;
+
+        match serde_json::from_value::<Option<Vec<serde_json::Value>>>(value)? {
+            Some(receipts) if receipts.len() == block.transactions.len() => {
+                Ok(Some(receipts.into_iter().map(parse_receipt).collect::<Result<_>>()?))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+/// parse a receipt and the fields that web3 does not know
+fn parse_receipt(value: serde_json::Value) -> Result<(TransactionReceipt,TxExt)> {
+    let ext = serde_json::from_value::<TxExt>(value.clone())?;
+    Ok((serde_json::from_value::<TransactionReceipt>(value)?, TxExt::default().with_receipt(ext)))
+}
+
+/// check if the error is caused by the connection with the node
+fn is_transient_error(err: &Error) -> bool {
+    match err {
+        Error::Web3(err) => is_transient(err),
+        _ => false,
+    }
+}
+
+/// send a batch of calls to the same method, failing if any of them fails
+fn batch_call<T: BatchTransport>(
+    transport: &T,
+    method: &str,
+    params: Vec<Vec<serde_json::Value>>
+) -> Result<Vec<serde_json::Value>> {
+
+    let requests : Vec<_> = params.into_iter()
+        .map(|p| transport.prepare(method, p))
+        .collect();
+
+    let mut values = Vec::new();
+    for response in transport.send_batch(requests).wait()? {
+        values.push(response?);
+    }
+    Ok(values)
+}
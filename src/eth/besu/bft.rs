@@ -0,0 +1,107 @@
+use rlp::{Rlp, RlpStream};
+use keccak_hash::keccak;
+use web3::types::{Address, Block};
+
+use super::super::types::{recover_signer, BlockExt};
+
+/// A vote of the proposer to add or remove a validator
+#[derive(Debug, Clone, PartialEq)]
+pub struct BftVote {
+    pub recipient : Address,
+    pub authorize : bool,
+}
+
+/// The IBFT 2.0 and QBFT extraData, RLP([vanity, validators, vote, round, seals])
+#[derive(Debug, Clone, PartialEq)]
+pub struct BftExtra {
+    pub validators : Vec<Address>,
+    pub vote : Option<BftVote>,
+    pub round : u64,
+    pub seals : Vec<Vec<u8>>,
+    /// the extraData without the committed seals, as signed by the committers
+    unsealed : Vec<u8>,
+}
+
+/// parse the extraData of an IBFT 2.0 or QBFT block, in QBFT the seals are
+///   replaced by an empty list when signing instead of being removed
+pub fn parse_bft_extra(extra : &[u8], qbft: bool) -> Option<BftExtra> {
+    let rlp = Rlp::new(extra);
+    if !rlp.is_list() || rlp.item_count().ok()? != 5 {
+        return None;
+    }
+
+    let validators : Vec<Address> = rlp.at(1).ok()?.as_list().ok()?;
+
+    let vote_rlp = rlp.at(2).ok()?;
+    let vote = if vote_rlp.is_list() && vote_rlp.item_count().ok()? == 2 {
+        let vote_type : Vec<u8> = vote_rlp.at(1).ok()?.as_val().ok()?;
+        Some(BftVote {
+            recipient : vote_rlp.at(0).ok()?.as_val().ok()?,
+            authorize : vote_type.first() == Some(&0xff),
+        })
+    } else {
+        None
+    };
+
+    // IBFT 2.0 encodes the round as 4 bytes, QBFT as a scalar
+    let round = rlp.at(3).ok()?.data().ok()?.iter()
+        .fold(0u64, |round, b| (round << 8) | u64::from(*b));
+
+    let seals_rlp = rlp.at(4).ok()?;
+    let seals = seals_rlp.iter()
+        .map(|seal| seal.data().map(|d| d.to_vec()).ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let mut stream = RlpStream::new_list(if qbft { 5 } else { 4 });
+    for i in 0..4 {
+        stream.append_raw(rlp.at(i).ok()?.as_raw(), 1);
+    }
+    if qbft {
+        stream.begin_list(0);
+    }
+
+    Some(BftExtra {
+        validators,
+        vote,
+        round,
+        seals,
+        unsealed : stream.out(),
+    })
+}
+
+/// recover the validators that committed the block from the committed seals,
+///   ext has the header fields added after london
+#[allow(deprecated)]
+pub fn parse_bft_committers<T>(block : &Block<T>, ext : Option<&BlockExt>, extra : &BftExtra) -> Vec<Address> {
+    let (number, mix_hash, nonce) = match (block.number, block.mix_hash, block.nonce) {
+        (Some(number), Some(mix_hash), Some(nonce)) => (number, mix_hash, nonce),
+        _ => return Vec::new(),
+    };
+
+    let ext_len = ext.map(|ext| ext.header_len()).unwrap_or(0);
+    let mut stream = RlpStream::new_list(15 + ext_len);
+    stream
+        .append(&block.parent_hash)
+        .append(&block.uncles_hash)
+        .append(&block.author)
+        .append(&block.state_root)
+        .append(&block.transactions_root)
+        .append(&block.receipts_root)
+        .append(&block.logs_bloom)
+        .append(&block.difficulty)
+        .append(&number)
+        .append(&block.gas_limit)
+        .append(&block.gas_used)
+        .append(&block.timestamp)
+        .append(&extra.unsealed)
+        .append(&mix_hash)
+        .append(&nonce);
+    if let Some(ext) = ext {
+        ext.append_header(&mut stream);
+    }
+    let seal_hash = keccak(&stream.out());
+
+    extra.seals.iter()
+        .filter_map(|seal| recover_signer(seal, &seal_hash))
+        .collect()
+}
@@ -13,6 +13,7 @@ pub enum Error {
     FunctionNotFound,
     CodeDoesNotMatch,
     CompilerNotFound,
+    CompilerFailed(String),
     EventNotFound,
     Io(std::io::Error),
 }
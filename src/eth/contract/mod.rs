@@ -1,12 +1,19 @@
 mod parser;
 mod verifier;
+mod storage;
+mod sourcemap;
 mod error;
 
 pub use self::{
     error::Error,
     parser::ContractParser,
+    parser::RevertInfo,
+    parser::panic_reason,
     verifier::installed_compilers,
+    verifier::SolcContract,
     verifier::verify_abi,
     verifier::compile_and_verify,
-    verifier::ONLY_ABI
+    verifier::ONLY_ABI,
+    storage::storage_slot_names,
+    sourcemap::SourceMap
 };
\ No newline at end of file
@@ -9,8 +9,36 @@ use super::error::{Error,Result};
 
 static FALLBACK : &str = "()";
 
+/// selectors of the builtin Error(string) and Panic(uint256) errors
+const ERROR_SELECTOR : [u8;4] = [0x08, 0xc3, 0x79, 0xa0];
+const PANIC_SELECTOR : [u8;4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// A solidity custom error, not supported by ethabi
+#[derive(Deserialize, Debug)]
+pub struct AbiError {
+    pub name : String,
+    #[serde(default)]
+    pub inputs : Vec<ethabi::Param>,
+}
+
+/// load an abi, returning the custom errors apart since ethabi fails
+///   with unknown entry types
+pub fn load_abi(abistr: &str) -> Result<(ethabi::Contract,Vec<AbiError>)> {
+    let entries : Vec<serde_json::Value> = serde_json::from_str(abistr)?;
+    let (errors, entries) : (Vec<_>,Vec<_>) = entries.into_iter()
+        .filter(|e| e["type"] != "receive")
+        .partition(|e| e["type"] == "error");
+
+    let contract = ethabi::Contract::load(serde_json::to_string(&entries)?.as_bytes())?;
+    let errors = errors.into_iter()
+        .filter_map(|e| serde_json::from_value(e).ok())
+        .collect();
+    Ok((contract,errors))
+}
+
 pub struct ContractParser {
     pub abis : HashMap<Address,ethabi::Contract>,
+    pub errors : HashMap<Address,Vec<AbiError>>,
 }
 
 pub struct CallInfo<'a> {
@@ -18,20 +46,86 @@ pub struct CallInfo<'a> {
     pub params : Vec<(&'a String,ethabi::Token)>,
 }
 
+/// The decoded revert data of a failed call
+pub enum RevertInfo<'a> {
+    Empty,
+    Error(String),
+    Panic(u64),
+    Custom(CallInfo<'a>),
+    Unknown(Vec<u8>),
+}
+
+/// get the description of a Panic(uint256) code
+pub fn panic_reason(code: u64) -> &'static str {
+    match code {
+        0x00 => "generic compiler panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic overflow or underflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid storage byte array encoding",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to zero-initialized function",
+        _ => "unknown panic code",
+    }
+}
+
 impl ContractParser {
     
     /// create a new contract parser
     pub fn new() -> Self {
-        ContractParser { abis : HashMap::new() }
+        ContractParser { abis : HashMap::new(), errors : HashMap::new() }
     }
     
     /// add a new contract and its abi
     pub fn add(&mut self, addr: Address, abistr : &str) -> Result<()> {
-        self.abis.entry(addr)
-            .or_insert(ethabi::Contract::load(abistr.as_bytes())?);
+        if !self.abis.contains_key(&addr) {
+            let (contract, errors) = load_abi(abistr)?;
+            self.abis.insert(addr, contract);
+            self.errors.insert(addr, errors);
+        }
         Ok(())
     }
 
+    /// decode the data returned by a reverted call, custom errors are
+    ///   only decoded if the contract abi has been added. Data that cannot
+    ///   be decoded is returned as unknown
+    pub fn revert_reason(&self, addr: Option<&Address>, data: &[u8]) -> Result<RevertInfo> {
+        if data.is_empty() {
+            return Ok(RevertInfo::Empty);
+        }
+        if data.len() < 4 {
+            return Ok(RevertInfo::Unknown(data.to_vec()));
+        }
+        if data[0..4] == ERROR_SELECTOR {
+            if let Some(ethabi::Token::String(reason)) = ethabi::decode(&[ParamType::String], &data[4..]).ok().and_then(|mut t| t.pop()) {
+                return Ok(RevertInfo::Error(reason));
+            }
+        }
+        if data[0..4] == PANIC_SELECTOR {
+            if let Some(ethabi::Token::Uint(code)) = ethabi::decode(&[ParamType::Uint(256)], &data[4..]).ok().and_then(|mut t| t.pop()) {
+                return Ok(RevertInfo::Panic(code.low_u64()));
+            }
+        }
+        if let Some(errors) = addr.and_then(|addr| self.errors.get(addr)) {
+            for error in errors {
+                let paramtypes : &Vec<ParamType> = &error.inputs.iter().map(|p| p.kind.clone()).collect();
+                if data[0..4] == short_signature(&error.name,&paramtypes)[..] {
+                    if let Ok(tokens) = ethabi::decode(&paramtypes, &data[4..]) {
+                        let params = error.inputs.iter()
+                            .map(|input| &input.name)
+                            .zip(tokens)
+                            .collect::<Vec<_>>();
+                        return Ok(RevertInfo::Custom(CallInfo { func : &error.name, params }));
+                    }
+                }
+            }
+        }
+        Ok(RevertInfo::Unknown(data.to_vec()))
+    }
+
     /// return true if the contract has been already added
     pub fn contains(&self, addr: &Address)-> bool {
         self.abis.contains_key(addr)
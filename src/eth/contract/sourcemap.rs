@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+/// A source range, as described in the solc source mappings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceRange {
+    pub start : i64,
+    pub length : i64,
+    pub file : i64,
+}
+
+/// The solc srcmap-runtime, with one source range for each instruction
+#[derive(Debug)]
+pub struct SourceMap {
+    ranges : Vec<SourceRange>,
+    instructions : HashMap<usize,usize>,
+    file : i64,
+}
+
+/// get the instruction index of each pc, PUSH1..PUSH32 operands are not instructions
+fn instruction_indexes(code: &[u8]) -> HashMap<usize,usize> {
+    let mut indexes = HashMap::new();
+    let mut pc = 0;
+    let mut index = 0;
+    while pc < code.len() {
+        indexes.insert(pc, index);
+        let op = code[pc];
+        if op >= 0x60 && op <= 0x7f {
+            pc += (op - 0x5f) as usize;
+        }
+        pc += 1;
+        index += 1;
+    }
+    indexes
+}
+
+impl SourceMap {
+
+    /// parse the compressed s:l:f:j;... format, empty fields are the same as
+    ///   in the previous entry. file is the index of the source to map to
+    pub fn parse(srcmap: &str, code: &[u8], file: i64) -> Self {
+        let mut ranges = Vec::new();
+        let mut last = SourceRange { start: -1, length: -1, file: -1 };
+        for entry in srcmap.split(';') {
+            let mut fields = entry.split(':');
+            let mut next = |prev: i64| {
+                fields.next()
+                    .filter(|f| !f.is_empty())
+                    .and_then(|f| f.parse().ok())
+                    .unwrap_or(prev)
+            };
+            last = SourceRange {
+                start : next(last.start),
+                length : next(last.length),
+                file : next(last.file),
+            };
+            ranges.push(last);
+        }
+        SourceMap { ranges, instructions : instruction_indexes(code), file }
+    }
+
+    /// get the source range of the instruction at pc, if it maps to the source
+    pub fn range(&self, pc: usize) -> Option<SourceRange> {
+        self.instructions.get(&pc)
+            .and_then(|index| self.ranges.get(*index))
+            .filter(|range| range.file == self.file && range.start >= 0)
+            .cloned()
+    }
+
+    /// get the line number and text of the source that matches the instruction at pc
+    pub fn source_line(&self, source: &str, pc: usize) -> Option<(usize,String)> {
+        let range = self.range(pc)?;
+        let start = range.start as usize;
+        if start > source.len() || !source.is_char_boundary(start) {
+            return None;
+        }
+        let line_no = source[..start].matches('\n').count() + 1;
+        let line = source.lines().nth(line_no - 1).unwrap_or("");
+        Some((line_no, line.trim().to_string()))
+    }
+}
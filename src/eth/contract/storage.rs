@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use web3::types::U256;
+
+use super::error::Result;
+
+/// State variable in the solc storage-layout output
+#[derive(Serialize, Deserialize, Debug)]
+struct StorageEntry {
+    label : String,
+    slot : String,
+    offset : u64,
+}
+
+/// The solc storage-layout output
+#[derive(Serialize, Deserialize, Debug)]
+struct StorageLayout {
+    storage : Vec<StorageEntry>,
+}
+
+/// get the names of the state variables stored in each slot, variables packed
+///   in the same slot are joined. Slots of mappings and dynamic arrays are
+///   hashes, so only their base slot is named
+pub fn storage_slot_names(layout: &str) -> Result<HashMap<U256,String>> {
+    let layout : StorageLayout = serde_json::from_str(layout)?;
+    let mut names : HashMap<U256,String> = HashMap::new();
+    for entry in layout.storage {
+        if let Ok(slot) = U256::from_dec_str(&entry.slot) {
+            let name = names.entry(slot).or_insert_with(String::new);
+            if !name.is_empty() {
+                name.push_str(", ");
+            }
+            if entry.offset > 0 {
+                name.push_str(&format!("{}@{}", entry.label, entry.offset));
+            } else {
+                name.push_str(&entry.label);
+            }
+        }
+    }
+    Ok(names)
+}
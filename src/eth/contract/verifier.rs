@@ -12,6 +12,7 @@ use ethabi;
 
 use bootstrap::Config;
 use super::error::{Error,Result};
+use super::parser::load_abi;
 
 pub static ONLY_ABI : &str = "abi-only";
 
@@ -20,12 +21,27 @@ pub struct SolcContract {
     pub abi : String,
     #[serde(rename = "bin-runtime")]
     pub binruntime : String,
+    #[serde(rename = "srcmap-runtime", default)]
+    pub srcmap : Option<String>,
+    #[serde(rename = "storage-layout", default)]
+    pub storage_layout : Option<serde_json::Value>,
+    /// index of the compiled source in the file field of the srcmap
+    #[serde(skip)]
+    pub srcmap_file : i64,
 }
 
+/// combined-json outputs, storage-layout is only available since solc 0.5.13
+const SOLC_OUTPUTS : [&str;2] = [
+    "abi,bin-runtime,srcmap-runtime,storage-layout",
+    "abi,bin-runtime,srcmap-runtime"
+];
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SolcJson {
     contracts : HashMap<String,SolcContract>,
     version   : String,
+    #[serde(rename = "sourceList", default)]
+    source_list : Vec<String>,
 }
 
 /// get the solc complilers intalled in config solc_path
@@ -44,11 +60,12 @@ pub fn installed_compilers(cfg : &Config) -> Result<Vec<String>> {
 
 /// verify if an abi is ok
 pub fn verify_abi(source: &str) -> Result<()>{
-    ethabi::Contract::load(source.as_bytes())?;
+    load_abi(source)?;
     Ok(())
 }
 
-/// compile a contract and check if it maches with blockchain bytecode
+/// compile a contract and check if it maches with blockchain bytecode,
+///   returning the compiled contract
 pub fn compile_and_verify(
     cfg : &Config,
     source: &str,
@@ -57,7 +74,7 @@ pub fn compile_and_verify(
     optimized: bool,
     code: &[u8])
 
--> Result<String> {
+-> Result<SolcContract> {
 
     if installed_compilers(&cfg)?.into_iter().find(|c| c==compiler).is_none() {
         return Err(Error::CompilerNotFound);
@@ -79,28 +96,43 @@ pub fn compile_and_verify(
 
     File::create(&input)?.write_all(source.as_bytes())?;
 
-    let args : Vec<&str> = if optimized {
-        vec![&input,"-o",&tmp_dir,"--combined-json","abi,bin-runtime","--optimize","--optimize-runs","200"]
-    } else {
-        vec![&input,"-o",&tmp_dir,"--combined-json","abi,bin-runtime","--optimize-runs","200"]
-    };
-
-    let cmdoutput = Command::new(
-        format!("{}/{}",&cfg.solc_path.clone().unwrap(),compiler)
-    ).args(args).output()?;
-    
-    println!("stdout: {}", String::from_utf8_lossy(&cmdoutput.stdout));
-    println!("stderr: {}", String::from_utf8_lossy(&cmdoutput.stderr));
-
+    // older compilers fail with unknown outputs, try until one works
     let mut contents = String::new();
-    File::open(&output)?.read_to_string(&mut contents)?;
+    let mut solc_error = String::new();
+    for outputs in SOLC_OUTPUTS.iter() {
+        let args : Vec<&str> = if optimized {
+            vec![&input,"-o",&tmp_dir,"--combined-json",outputs,"--optimize","--optimize-runs","200"]
+        } else {
+            vec![&input,"-o",&tmp_dir,"--combined-json",outputs,"--optimize-runs","200"]
+        };
+
+        let cmdoutput = Command::new(
+            format!("{}/{}",&cfg.solc_path.clone().unwrap(),compiler)
+        ).args(args).output()?;
+        
+        debug!("solc {} stdout: {}", outputs, String::from_utf8_lossy(&cmdoutput.stdout));
+        debug!("solc {} stderr: {}", outputs, String::from_utf8_lossy(&cmdoutput.stderr));
+
+        if cmdoutput.status.success() {
+            File::open(&output)?.read_to_string(&mut contents)?;
+            break;
+        }
+        solc_error = String::from_utf8_lossy(&cmdoutput.stderr).into_owned();
+    }
+    if contents.is_empty() {
+        return Err(Error::CompilerFailed(solc_error));
+    }
 
     let deserialized: SolcJson = serde_json::from_str(&contents)?;
     let key = format!("{}:{}",&input,contractname);
 
     if let Some(contract) = deserialized.contracts.get(&key) {
         code_equals(&contract, &code)?;
-        Ok(contract.abi.clone()) 
+        // imported and compiler generated sources have other indexes
+        let srcmap_file = deserialized.source_list.iter()
+            .position(|source| *source == input)
+            .unwrap_or(0) as i64;
+        Ok(SolcContract { srcmap_file, ..contract.clone() })
     } else {
         Err(Error::ContractNotFound)
     }
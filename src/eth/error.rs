@@ -1,5 +1,6 @@
 use db;
 use std::io;
+use web3::types::H256;
 
 #[derive(Debug)]
 pub enum Error {
@@ -9,6 +10,8 @@ pub enum Error {
     EthAbi(ethabi::Error),
     SerdeJson(serde_json::Error),
     Io(std::io::Error),
+    ReceiptNotFound(H256),
+    InvalidGenesis(String),
 }
 
 impl From<io::Error> for Error {
@@ -0,0 +1,59 @@
+use web3::api::Namespace;
+use web3::helpers::{CallFuture};
+use web3::types::{Block, BlockNumber, H256};
+use web3::Transport;
+
+use super::types::TxExt;
+
+/// `EthExt` namespace, eth_ calls read into the fields not available in web3::types
+#[derive(Debug, Clone)]
+pub struct EthExt<T> {
+    transport: T,
+}
+
+/// A transport for the eth_ calls with the fields not available in web3::types
+impl<T: Transport> Namespace<T> for EthExt<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        EthExt { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> EthExt<T> {
+
+    /// Retrieve an uncle header by calling eth_getUncleByBlockNumberAndIndex
+    pub fn uncle(&self, blockno: u64, index: u64) -> CallFuture<Option<Block<H256>>, T::Out> {
+        CallFuture::new(
+            self.transport.execute(
+                "eth_getUncleByBlockNumberAndIndex",
+                vec![
+                    web3::helpers::serialize(&BlockNumber::Number(blockno)),
+                    web3::helpers::serialize(&format!("0x{:x}", index)),
+                ]
+        ))
+    }
+
+    /// Retrieve the typed transaction fields by calling eth_getTransactionByHash
+    pub fn tx_ext(&self, tx: &H256) -> CallFuture<Option<TxExt>, T::Out> {
+        CallFuture::new(
+            self.transport.execute(
+                "eth_getTransactionByHash",
+                vec![web3::helpers::serialize(tx)]
+        ))
+    }
+
+    /// Retrieve the effective gas price and blob gas by calling eth_getTransactionReceipt
+    pub fn receipt_ext(&self, tx: &H256) -> CallFuture<Option<TxExt>, T::Out> {
+        CallFuture::new(
+            self.transport.execute(
+                "eth_getTransactionReceipt",
+                vec![web3::helpers::serialize(tx)]
+        ))
+    }
+}
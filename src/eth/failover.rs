@@ -0,0 +1,195 @@
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use web3::futures::{future, Future};
+use web3::helpers::build_request;
+use web3::transports::EventLoopHandle;
+use web3::{rpc, BatchTransport, ErrorKind, RequestId, Transport};
+
+use super::transport::AnyTransport;
+
+/// retries of a request before giving up
+const MAX_RETRIES : u32 = 8;
+
+/// backoff after all endpoints failed, doubled on each retry
+pub const INITIAL_BACKOFF_MS : u64 = 250;
+pub const MAX_BACKOFF_MS : u64 = 8_000;
+
+/// check if the error is caused by the connection with the node and the
+///   request can be retried, or is an error returned by the node itself
+pub fn is_transient(err: &web3::Error) -> bool {
+    match err.kind() {
+        ErrorKind::Transport(_) | ErrorKind::Io(_) | ErrorKind::Unreachable => true,
+        _ => false,
+    }
+}
+
+/// An endpoint of the failover transport, if it cannot be opened it is
+///   connected again when it is used
+struct Endpoint {
+    transport : Mutex<Option<AnyTransport>>,
+    eloops : Mutex<Vec<EventLoopHandle>>,
+}
+
+impl fmt::Debug for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Endpoint")
+            .field("transport", &self.transport)
+            .finish()
+    }
+}
+
+impl Endpoint {
+
+    /// get the transport of the endpoint, opening it if it is not open yet
+    fn transport(&self, url: &str) -> web3::Result<AnyTransport> {
+        let mut transport = self.transport.lock().unwrap();
+        if transport.is_none() {
+            let (eloop, opened) = AnyTransport::new(url)?;
+            info!("Opened web3 endpoint {}", url);
+            self.eloops.lock().unwrap().push(eloop);
+            *transport = Some(opened);
+        }
+        Ok(transport.clone().unwrap())
+    }
+}
+
+/// A transport over a list of endpoints. When a request fails with a transient
+///   error it is retried in the next endpoint, and once all endpoints failed,
+///   retried again after an exponential backoff
+#[derive(Debug, Clone)]
+pub struct FailoverTransport {
+    urls : Arc<Vec<String>>,
+    endpoints : Arc<Vec<Endpoint>>,
+    current : Arc<AtomicUsize>,
+    next_id : Arc<AtomicUsize>,
+    max_retries : u32,
+}
+
+impl FailoverTransport {
+
+    /// create the transports for all the urls, the first one is the preferred.
+    ///   The endpoints that cannot be opened are opened again when used, it
+    ///   only fails if none of them can be opened
+    pub fn new(urls: &[String]) -> web3::Result<(Vec<EventLoopHandle>, Self)> {
+        let mut eloops = Vec::new();
+        let mut endpoints = Vec::new();
+        let mut last_err = None;
+        for url in urls {
+            let transport = match AnyTransport::new(url) {
+                Ok((eloop, transport)) => {
+                    eloops.push(eloop);
+                    Some(transport)
+                }
+                Err(err) => {
+                    warn!("Cannot open web3 endpoint {}: {}", url, err);
+                    last_err = Some(err);
+                    None
+                }
+            };
+            endpoints.push(Endpoint {
+                transport : Mutex::new(transport),
+                eloops : Mutex::new(Vec::new()),
+            });
+        }
+        if eloops.is_empty() {
+            if let Some(err) = last_err {
+                return Err(err);
+            }
+        }
+        Ok((eloops, FailoverTransport {
+            urls : Arc::new(urls.to_vec()),
+            endpoints : Arc::new(endpoints),
+            current : Arc::new(AtomicUsize::new(0)),
+            next_id : Arc::new(AtomicUsize::new(1)),
+            max_retries : MAX_RETRIES,
+        }))
+    }
+
+    /// try each endpoint only once, for requests that cannot wait for
+    ///   the node to recover
+    pub fn without_backoff(mut self) -> Self {
+        self.max_retries = self.endpoints.len().saturating_sub(1) as u32;
+        self
+    }
+
+    /// check which endpoints are responding and select the first healthy one,
+    ///   so the preferred endpoint is used again when it recovers
+    pub fn health_check(&self) -> Vec<(String,bool)> {
+        let health : Vec<_> = self.endpoints.iter()
+            .zip(self.urls.iter())
+            .map(|(endpoint, url)| endpoint.transport(url)
+                .and_then(|t| t.execute("eth_blockNumber", vec![]).wait())
+                .is_ok())
+            .collect();
+
+        if let Some(index) = health.iter().position(|healthy| *healthy) {
+            if self.current.swap(index, Ordering::SeqCst) != index {
+                info!("Using web3 endpoint {}", self.urls[index]);
+            }
+        }
+
+        self.urls.iter().cloned().zip(health.into_iter()).collect()
+    }
+
+    /// call the current endpoint, retrying with the others if it fails
+    fn with_retry<F,R>(&self, f: F) -> web3::Result<R>
+    where
+        F: Fn(&AnyTransport) -> web3::Result<R>
+    {
+        let mut backoff = INITIAL_BACKOFF_MS;
+        let mut retries = 0;
+        loop {
+            let index = self.current.load(Ordering::SeqCst) % self.endpoints.len();
+            match self.endpoints[index].transport(&self.urls[index]).and_then(|t| f(&t)) {
+                Err(ref err) if is_transient(err) && retries < self.max_retries => {
+                    let next = (index + 1) % self.endpoints.len();
+                    warn!("web3 endpoint {} failed: {}, retrying with {}", self.urls[index], err, self.urls[next]);
+                    let _ = self.current.compare_exchange(index, next, Ordering::SeqCst, Ordering::SeqCst);
+
+                    // all endpoints have been tried, wait before start again
+                    retries += 1;
+                    if retries as usize % self.endpoints.len() == 0 {
+                        thread::sleep(Duration::from_millis(backoff));
+                        backoff = (backoff * 2).min(MAX_BACKOFF_MS);
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Transport for FailoverTransport {
+    type Out = Box<dyn Future<Item = rpc::Value, Error = web3::Error>>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        // the request can be sent to any endpoint, so the ids are not taken
+        //   from the endpoints, that could be not opened yet
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        (id, build_request(id, method, params))
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        Box::new(future::result(
+            self.with_retry(|t| t.send(id, request.clone()).wait())
+        ))
+    }
+}
+
+impl BatchTransport for FailoverTransport {
+    type Batch = Box<dyn Future<Item = Vec<Result<rpc::Value, web3::Error>>, Error = web3::Error>>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        let requests : Vec<_> = requests.into_iter().collect();
+        Box::new(future::result(
+            self.with_retry(|t| t.send_batch(requests.clone()).wait())
+        ))
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+
+use web3::types::{Address, H256, U256};
+
+use super::error::{Error, Result};
+use super::types::{hex_to_addr, hex_to_u256, hex_to_vec, GenesisAlloc};
+
+/// An account in the alloc of a genesis.json, quantities can be in hex or
+///   in decimal
+#[derive(Deserialize)]
+struct GenesisAccount {
+    #[serde(default)]
+    balance : Option<String>,
+    #[serde(default)]
+    nonce : Option<String>,
+    #[serde(default)]
+    code : Option<String>,
+    #[serde(default)]
+    storage : BTreeMap<String,String>,
+}
+
+/// The fork blocks of the config of a genesis.json
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisForks {
+    #[serde(default)]
+    pub byzantium_block : Option<u64>,
+    #[serde(default)]
+    pub constantinople_block : Option<u64>,
+    #[serde(default)]
+    pub petersburg_block : Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct Genesis {
+    #[serde(default)]
+    config : GenesisForks,
+    #[serde(default)]
+    alloc : BTreeMap<String,GenesisAccount>,
+}
+
+/// read and parse a genesis.json
+fn read_genesis(path: &str) -> Result<Genesis> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+/// add the 0x prefix, that is optional in the alloc addresses
+fn with_prefix(s: &str) -> String {
+    format!("0x{}",s.trim_start_matches("0x"))
+}
+
+/// parse an hex or decimal quantity
+fn parse_quantity(s: &str) -> Result<U256> {
+    if s.starts_with("0x") {
+        Ok(hex_to_u256(s)?)
+    } else {
+        U256::from_dec_str(s).map_err(|_| Error::InvalidGenesis(format!("bad quantity {}",s)))
+    }
+}
+
+/// parse a storage key or value, that can be not zero-padded
+fn parse_word(s: &str) -> Result<H256> {
+    let mut word = [0u8; 32];
+    hex_to_u256(s)?.to_big_endian(&mut word);
+    Ok(H256::from(word))
+}
+
+/// read the allocations of a geth genesis.json, sorted by address
+pub fn read_genesis_alloc(path: &str) -> Result<Vec<(Address,GenesisAlloc)>> {
+
+    let genesis = read_genesis(path)?;
+
+    let mut allocs = Vec::new();
+    for (addr, account) in genesis.alloc {
+        let addr = with_prefix(&addr);
+        if addr.len() != 42 {
+            return Err(Error::InvalidGenesis(format!("bad address {}",addr)));
+        }
+        let mut storage = Vec::new();
+        for (key, value) in &account.storage {
+            storage.push((parse_word(key)?, parse_word(value)?));
+        }
+        allocs.push((hex_to_addr(&addr)?, GenesisAlloc {
+            balance : account.balance.as_ref().map(|b| parse_quantity(b)).unwrap_or(Ok(U256::zero()))?,
+            nonce : account.nonce.as_ref().map(|n| parse_quantity(n)).unwrap_or(Ok(U256::zero()))?,
+            code : account.code.as_ref().map(|c| hex_to_vec(&with_prefix(c))).unwrap_or(Ok(Vec::new()))?,
+            storage,
+        }));
+    }
+    allocs.sort_by_key(|(addr,_)| *addr);
+
+    Ok(allocs)
+}
+
+/// read the fork blocks of a geth genesis.json
+pub fn read_genesis_forks(path: &str) -> Result<GenesisForks> {
+    Ok(read_genesis(path)?.config)
+}
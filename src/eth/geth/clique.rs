@@ -1,7 +1,20 @@
+use std::fmt;
+
 use rlp::RlpStream;
 use keccak_hash::keccak;
-use ethkey::{Signature,recover};
-use web3::types::{H256, Address};
+use web3::types::{H64, H256, U256, Address, Block};
+
+use super::super::types::recover_signer;
+
+/// default number of blocks after which to checkpoint and reset the pending votes
+pub const DEFAULT_EPOCH : u64 = 30000;
+
+const EXTRA_VANITY : usize = 32;
+const EXTRA_SEAL : usize = 65;
+
+/// magic nonces to vote on adding or removing a signer
+const NONCE_AUTH : [u8;8] = [0xff;8];
+const NONCE_DROP : [u8;8] = [0x00;8];
 
 /* example header 
     -----------------------------------------------
@@ -18,10 +31,11 @@ use web3::types::{H256, Address};
     -----------------------------------------------
 */
 
-/// get the block author from the clique header
-#[allow(deprecated)]
-pub fn parse_clique_header<T>(block : &web3::types::Block<T>) -> Option<Address> {
-    const EXTRA_SEAL : usize = 65;
+/// get the hash signed by the clique sealer, the header without the seal
+pub fn seal_hash<T>(block : &web3::types::Block<T>) -> Option<H256> {
+    if block.extra_data.0.len() < EXTRA_SEAL {
+        return None;
+    }
     let vanity = &block.extra_data.0[..block.extra_data.0.len()-EXTRA_SEAL];
     let mut stream = RlpStream::new_list(15);
     stream
@@ -43,22 +57,217 @@ pub fn parse_clique_header<T>(block : &web3::types::Block<T>) -> Option<Address>
         
     let rlp = &stream.out();
 
-    let seal_hash = keccak(&rlp);
+    Some(keccak(&rlp))
+}
+
+/// get the block author from the clique header
+pub fn parse_clique_header<T>(block : &web3::types::Block<T>) -> Option<Address> {
+    let seal_hash = seal_hash(block)?;
 
     let signature = &block.extra_data.0[block.extra_data.0.len()-EXTRA_SEAL..];
 
-    let r = H256::from_slice(&signature[0..32]);
-    let s = H256::from_slice(&signature[32..]);
-    let v = signature[64];
-
-    let sig = Signature::from_rsv(&r, &s, v);
- 
-    if sig.is_valid() {
-        if let Ok(pbk) = recover(&sig, &seal_hash) {
-            let pbk_hash = keccak(pbk);
-            let signer = Address::from_slice(&pbk_hash.0[12..]);
-            return Some(signer);
+    recover_signer(signature, &seal_hash)
+}
+
+/// get the signers list of a checkpoint block
+#[allow(deprecated)]
+pub fn parse_checkpoint_signers<T>(block : &Block<T>) -> Vec<Address> {
+    let extra = &block.extra_data.0;
+    if extra.len() < EXTRA_VANITY + EXTRA_SEAL {
+        return Vec::new();
+    }
+    extra[EXTRA_VANITY..extra.len()-EXTRA_SEAL]
+        .chunks(20)
+        .filter(|chunk| chunk.len() == 20)
+        .map(Address::from_slice)
+        .collect()
+}
+
+/// A vote of a signer to add or remove the target from the signers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Vote {
+    pub signer : Address,
+    pub target : Address,
+    pub authorize : bool,
+    pub block : u64,
+}
+
+/// A signer added or removed from the signers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignerChange {
+    pub signer : Address,
+    pub added : bool,
+}
+
+/// A block seal that does not follow the clique rules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SealViolation {
+    /// the signer cannot be recovered from the seal
+    InvalidSeal,
+    /// the signer is not in the signers list
+    Unauthorized(Address),
+    /// the signer already sealed one of the last signers/2+1 blocks
+    RecentlySigned(Address, u64),
+    /// the difficulty does not match the turn of the signer
+    WrongDifficulty(Address),
+}
+
+impl fmt::Display for SealViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SealViolation::InvalidSeal =>
+                write!(f, "invalid seal"),
+            SealViolation::Unauthorized(signer) =>
+                write!(f, "0x{:x} is not an authorized signer", signer),
+            SealViolation::RecentlySigned(signer, block) =>
+                write!(f, "0x{:x} recently sealed block {}", signer, block),
+            SealViolation::WrongDifficulty(signer) =>
+                write!(f, "wrong difficulty for the turn of 0x{:x}", signer),
+        }
+    }
+}
+
+/// The authorized signers, pending votes and recent signers after a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub number : u64,
+    pub signers : Vec<Address>,
+    pub votes : Vec<Vote>,
+    #[serde(default)]
+    pub recents : Vec<(u64,Address)>,
+}
+
+impl Snapshot {
+
+    /// create a snapshot from the signers list of a checkpoint block
+    pub fn from_checkpoint<T>(block : &Block<T>) -> Self {
+        let mut signers = parse_checkpoint_signers(block);
+        signers.sort();
+        Snapshot {
+            number : block.number.unwrap().low_u64(),
+            signers,
+            votes : Vec::new(),
+            recents : Vec::new(),
+        }
+    }
+
+    /// check if an address is an authorized signer
+    pub fn is_signer(&self, addr: &Address) -> bool {
+        self.signers.binary_search(addr).is_ok()
+    }
+
+    /// number of blocks that a signer has to wait to seal again
+    fn recents_limit(&self) -> u64 {
+        self.signers.len() as u64 / 2 + 1
+    }
+
+    /// check the seal of the block that follows the snapshot
+    pub fn verify_seal<T>(&self, block : &Block<T>) -> Option<SealViolation> {
+        let number = block.number.unwrap().low_u64();
+        if number == 0 {
+            return None;
+        }
+        let signer = match parse_clique_header(block) {
+            Some(signer) => signer,
+            None => return Some(SealViolation::InvalidSeal),
+        };
+        if !self.is_signer(&signer) {
+            return Some(SealViolation::Unauthorized(signer));
+        }
+        let limit = self.recents_limit();
+        if let Some((seen,_)) = self.recents.iter().find(|(seen,recent)| *recent == signer && seen + limit > number) {
+            return Some(SealViolation::RecentlySigned(signer, *seen));
+        }
+        let in_turn = self.signers[(number % self.signers.len() as u64) as usize] == signer;
+        if block.difficulty != U256::from(if in_turn { 2 } else { 1 }) {
+            return Some(SealViolation::WrongDifficulty(signer));
+        }
+        None
+    }
+
+    /// apply the vote of the block, returning the changes in the signers
+    pub fn apply<T>(&mut self, block : &Block<T>, epoch: u64) -> Vec<SignerChange> {
+        let number = block.number.unwrap().low_u64();
+        self.number = number;
+        let signer = parse_clique_header(block);
+
+        // signers can seal again once the limit of blocks has passed
+        let limit = self.recents_limit();
+        self.recents.retain(|(seen,_)| seen + limit > number);
+        if let Some(signer) = signer {
+            if number > 0 {
+                self.recents.push((number, signer));
+            }
+        }
+
+        // checkpoints reset the votes and list the signers
+        if number % epoch == 0 {
+            let mut signers = parse_checkpoint_signers(block);
+            signers.sort();
+            let mut changes : Vec<SignerChange> = signers.iter()
+                .filter(|s| !self.is_signer(s))
+                .map(|s| SignerChange { signer: *s, added: true })
+                .collect();
+            changes.extend(self.signers.iter()
+                .filter(|s| signers.binary_search(s).is_err())
+                .map(|s| SignerChange { signer: *s, added: false }));
+            self.signers = signers;
+            self.votes.clear();
+            return changes;
+        }
+
+        let authorize = match block.nonce {
+            Some(nonce) if nonce == H64::from(NONCE_AUTH) => true,
+            Some(nonce) if nonce == H64::from(NONCE_DROP) => false,
+            _ => return Vec::new(),
+        };
+        let target = block.author;
+        let signer = match signer {
+            Some(signer) => signer,
+            None => return Vec::new(),
+        };
+        if target == Address::default() || !self.is_signer(&signer) {
+            return Vec::new();
+        }
+
+        // a new vote replaces the previous one, and only votes that change
+        //   the signers are counted
+        self.votes.retain(|v| !(v.signer == signer && v.target == target));
+        if authorize != self.is_signer(&target) {
+            self.votes.push(Vote { signer, target, authorize, block : number });
+        }
+
+        let tally = self.votes.iter()
+            .filter(|v| v.target == target && v.authorize == authorize)
+            .count();
+        if tally <= self.signers.len() / 2 {
+            return Vec::new();
+        }
+
+        if authorize {
+            self.signers.push(target);
+            self.signers.sort();
+        } else {
+            self.signers.retain(|s| *s != target);
+            self.votes.retain(|v| v.signer != target);
+            let limit = self.recents_limit();
+            self.recents.retain(|(seen,_)| seen + limit > number);
+        }
+        self.votes.retain(|v| v.target != target);
+
+        vec![SignerChange { signer : target, added : authorize }]
+    }
+
+    /// get the pending proposals with the signers that voted them
+    pub fn proposals(&self) -> Vec<(Address,bool,Vec<Address>)> {
+        let mut proposals : Vec<(Address,bool,Vec<Address>)> = Vec::new();
+        for vote in &self.votes {
+            if let Some(p) = proposals.iter_mut().find(|p| p.0 == vote.target && p.1 == vote.authorize) {
+                p.2.push(vote.signer);
+                continue;
+            }
+            proposals.push((vote.target, vote.authorize, vec![vote.signer]));
         }
+        proposals
     }
-    None
 }
@@ -0,0 +1,44 @@
+use web3::types::U256;
+
+/// mainnet blocks where the block reward was reduced
+pub const MAINNET_BYZANTIUM : u64 = 4_370_000;
+pub const MAINNET_CONSTANTINOPLE : u64 = 7_280_000;
+
+/// The blocks where the ethash block reward changes
+#[derive(Debug, Clone, Copy)]
+pub struct RewardSchedule {
+    pub byzantium : u64,
+    pub constantinople : u64,
+}
+
+impl RewardSchedule {
+
+    /// get the static reward of a block, 5, 3 or 2 ether
+    pub fn block_reward(&self, blockno: u64) -> U256 {
+        let ether : u64 = if blockno >= self.constantinople {
+            2
+        } else if blockno >= self.byzantium {
+            3
+        } else {
+            5
+        };
+        U256::from(ether) * U256::from(10).pow(U256::from(18))
+    }
+
+    /// get the reward of the miner, with 1/32 of the block reward for each
+    ///   included uncle. Transaction fees are not included
+    pub fn miner_reward(&self, blockno: u64, uncles: usize) -> U256 {
+        let reward = self.block_reward(blockno);
+        reward + reward / U256::from(32) * U256::from(uncles)
+    }
+
+    /// get the reward of the miner of an uncle, that decreases with the
+    ///   distance to the including block
+    pub fn uncle_reward(&self, blockno: u64, uncle_blockno: u64) -> U256 {
+        let distance = blockno.saturating_sub(uncle_blockno);
+        if distance == 0 || distance > 7 {
+            return U256::zero();
+        }
+        self.block_reward(blockno) * U256::from(8 - distance) / U256::from(8)
+    }
+}
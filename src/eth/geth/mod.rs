@@ -1,2 +1,4 @@
 pub mod web3;
 pub mod clique;
+pub mod ethash;
+pub mod pos;
\ No newline at end of file
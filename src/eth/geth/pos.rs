@@ -0,0 +1,42 @@
+use web3::api::Namespace;
+use web3::helpers::{CallFuture};
+use web3::types::BlockNumber;
+use web3::Transport;
+
+use super::super::types::BlockExt;
+
+/// `Pos` namespace, eth_ calls that return the post-merge header fields
+#[derive(Debug, Clone)]
+pub struct Pos<T> {
+    transport: T,
+}
+
+/// A transport for the post-merge eth_ calls
+impl<T: Transport> Namespace<T> for Pos<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        Pos { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> Pos<T> {
+
+    /// Retrieve the post-merge fields and withdrawals of a block by calling
+    ///   eth_getBlockByNumber
+    pub fn block_ext(&self, blockno: u64) -> CallFuture<Option<BlockExt>, T::Out> {
+        CallFuture::new(
+            self.transport.execute(
+                "eth_getBlockByNumber",
+                vec![
+                    web3::helpers::serialize(&BlockNumber::Number(blockno)),
+                    web3::helpers::serialize(&false),
+                ]
+        ))
+    }
+}
@@ -1,6 +1,8 @@
+use std::collections::BTreeMap;
+
 use web3::api::Namespace;
 use web3::helpers::{CallFuture};
-use web3::types::{U256,Address,Transaction};
+use web3::types::{U256,Address,Transaction,H256};
 use web3::Transport;
 use rustc_hex::{FromHexError};
 
@@ -26,21 +28,38 @@ impl<T: Transport> Namespace<T> for Debug<T> {
     }
 }
 
-/// Entry returned by calltracer 
+/// Entry returned by calltracer, with its nested calls
 #[derive(Clone,Debug,Serialize,Deserialize)]
 pub struct DbgCallEntry {
     pub from: String,
+    #[serde(default)]
     pub input: String,
+    #[serde(default)]
     pub to: String,
     #[serde(rename = "type")] 
     pub op: String,
-    pub value: String,
+    pub value: Option<String>,
+    pub gas: Option<String>,
+    #[serde(rename = "gasUsed")]
+    pub gas_used: Option<String>,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub calls: Option<Vec<DbgCallEntry>>,
 }
 
 /// The structure returned by debug_traceTransaction(calltracer)
 #[derive(Debug,Serialize,Deserialize)]
 pub struct DbgInternalTxs {
     calls : Option<Vec<DbgCallEntry>>,
+    pub output : Option<String>,
+    pub error : Option<String>,
+}
+
+/// Entry returned by debug_traceBlockByNumber(calltracer) for each transaction
+#[derive(Debug,Serialize,Deserialize)]
+pub struct DbgBlockTrace {
+    pub result : Option<DbgInternalTxs>,
+    pub error : Option<String>,
 }
 
 impl DbgInternalTxs {
@@ -54,37 +73,122 @@ impl DbgInternalTxs {
         }
     }
 
+    /// Parse optional hex quantity
+    fn opthex_to_u256(&self, s:&Option<String>) -> Result<U256,FromHexError> {
+        match s {
+            Some(s) => hex_to_u256(s),
+            None => Ok(U256::default()),
+        }
+    }
+
+    /// Parse the calls and its nested calls in depth-first order
+    fn parse_calls(&self, calls: &[DbgCallEntry], depth: u32, parent: u64, itxs: &mut Vec<InternalTx>) -> Result<(),FromHexError> {
+        for call in calls {
+            let to = self.opthex_to_addr(&call.to)?;
+            let is_create = call.op == "CREATE" || call.op == "CREATE2";
+            itxs.push(InternalTx{
+                from      : hex_to_addr(&call.from)?,
+                to        : if is_create { None } else { to },
+                contract  : if is_create { to } else { None },
+                input     : hex_to_vec(&call.input)?,
+                value     : self.opthex_to_u256(&call.value)?,
+                depth,
+                parent,
+                call_type : call.op.clone(),
+                gas       : self.opthex_to_u256(&call.gas)?,
+                gas_used  : self.opthex_to_u256(&call.gas_used)?,
+                output    : hex_to_vec(call.output.as_ref().map(String::as_str).unwrap_or(""))?,
+                error     : call.error.clone(),
+            });
+            if let Some(subcalls) = &call.calls {
+                let itx_no = itxs.len() as u64;
+                self.parse_calls(subcalls, depth + 1, itx_no, itxs)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Parse debug_ call and return a vector of InternalTx
     pub fn parse(&self) -> Result<Vec<InternalTx>,FromHexError> {
-        let zero_u256 = U256::default();
         let mut itxs = Vec::new();
-        
         if let Some(calls) = &self.calls { 
-            for call in calls {
-                if call.op == "CREATE" || call.op == "CREATE2" {
-                    itxs.push(InternalTx{
-                        from     : hex_to_addr(&call.from)?,
-                        to       : None,
-                        contract : self.opthex_to_addr(&call.to)?,
-                        input    : hex_to_vec(&call.input)?,
-                        value    : if call.value == "0x0" { zero_u256 } else { hex_to_u256(&call.value)? }
-                    })
-                } else {
-                    itxs.push(InternalTx{
-                        from     : hex_to_addr(&call.from)?,
-                        to       : self.opthex_to_addr(&call.to)?,
-                        contract : None, 
-                        input    : hex_to_vec(&call.input)?,
-                        value    : if call.value == "0x0" { zero_u256 } else { hex_to_u256(&call.value)? }
-                    })
-                }
-            }
+            self.parse_calls(calls, 1, 0, &mut itxs)?;
         }
         Ok(itxs)
     }
 }
 
+/// Account fields returned by prestateTracer, only the changed ones in diffMode
+#[derive(Clone,Debug,Default,Serialize,Deserialize)]
+pub struct DbgAccountState {
+    pub balance: Option<String>,
+    pub nonce: Option<u64>,
+    pub code: Option<String>,
+    pub storage: Option<BTreeMap<String,String>>,
+}
+
+/// The structure returned by debug_traceTransaction(prestateTracer, diffMode)
+#[derive(Debug,Default,Serialize,Deserialize)]
+pub struct DbgStateDiff {
+    #[serde(default)]
+    pub pre: BTreeMap<String,DbgAccountState>,
+    #[serde(default)]
+    pub post: BTreeMap<String,DbgAccountState>,
+}
+
+/// Step of the execution returned by the default struct logger
+#[derive(Clone,Debug,Serialize,Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbgStructLog {
+    pub pc: usize,
+    pub op: String,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: u32,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub stack: Vec<String>,
+    #[serde(default)]
+    pub memory: Vec<String>,
+    #[serde(default)]
+    pub storage: BTreeMap<String,String>,
+}
+
+/// The structure returned by debug_traceTransaction without tracer
+#[derive(Debug,Serialize,Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbgStructLogs {
+    pub gas: u64,
+    pub failed: bool,
+    pub return_value: String,
+    pub struct_logs: Vec<DbgStructLog>,
+}
+
 impl<T: Transport> Debug<T> {
+
+    /// Retrieve the opcode-level trace by calling debug_traceTransaction
+    pub fn struct_logs(&self, tx: &H256) -> CallFuture<DbgStructLogs, T::Out> {
+        CallFuture::new(
+            self.transport.execute  (
+                "debug_traceTransaction",
+                vec![
+                    web3::helpers::serialize(tx),
+                    json!({"enableMemory":true,"disableStorage":false,"disableStack":false}),
+                ]
+        ))
+    }
+
+    /// Retrieve the changes in the state by calling debug_traceTransaction
+    pub fn state_diff(&self, tx: &H256) -> CallFuture<DbgStateDiff, T::Out> {
+        CallFuture::new(
+            self.transport.execute  (
+                "debug_traceTransaction",
+                vec![
+                    web3::helpers::serialize(tx),
+                    json!({"tracer":"prestateTracer","tracerConfig":{"diffMode":true}}),
+                ]
+        ))
+    }
     
     /// Retrieve internal transactions by calling debug_traceTransaction
     pub fn internal_txs(&self, tx: &Transaction) -> CallFuture<DbgInternalTxs, T::Out> {
@@ -1,9 +1,23 @@
 pub mod geth;
+pub mod parity;
+pub mod besu;
 
 mod reader;
+mod ext;
+mod txpool;
+mod batch;
+mod transport;
+mod failover;
 mod error;
+mod tests;
 pub mod contract;
+pub mod genesis;
 pub mod types;
 
 pub use self::error::{Error,Result};
 pub use self::reader::BlockchainReader;
+pub use self::batch::BatchReader;
+pub use self::ext::EthExt;
+pub use self::txpool::{TxPool, PoolTx, PoolStatus};
+pub use self::transport::subscribe_new_heads;
+pub use self::failover::{FailoverTransport,is_transient};
@@ -0,0 +1,51 @@
+use rlp::{Rlp, RlpStream};
+use keccak_hash::keccak;
+use web3::types::{Address, Block};
+
+use super::super::types::{recover_signer, BlockExt};
+
+/// The aura seal fields of a block
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuraSeal {
+    pub step : u64,
+    pub signature : Vec<u8>,
+    pub signer : Option<Address>,
+}
+
+/// get the step and signature of an aura block, the signer is recovered from
+///   the hash of the header without the seal fields. ext has the header
+///   fields added after london
+pub fn parse_aura_seal<T>(block : &Block<T>, ext : Option<&BlockExt>) -> Option<AuraSeal> {
+    if block.seal_fields.len() != 2 {
+        return None;
+    }
+    let step : u64 = Rlp::new(&block.seal_fields[0].0).as_val().ok()?;
+    let signature : Vec<u8> = Rlp::new(&block.seal_fields[1].0).as_val().ok()?;
+
+    let ext_len = ext.map(|ext| ext.header_len()).unwrap_or(0);
+    let mut stream = RlpStream::new_list(13 + ext_len);
+    stream
+        .append(&block.parent_hash)
+        .append(&block.uncles_hash)
+        .append(&block.author)
+        .append(&block.state_root)
+        .append(&block.transactions_root)
+        .append(&block.receipts_root)
+        .append(&block.logs_bloom)
+        .append(&block.difficulty)
+        .append(&block.number?)
+        .append(&block.gas_limit)
+        .append(&block.gas_used)
+        .append(&block.timestamp)
+        .append(&block.extra_data.0);
+    if let Some(ext) = ext {
+        ext.append_header(&mut stream);
+    }
+    let bare_hash = keccak(&stream.out());
+
+    Some(AuraSeal {
+        step,
+        signer : recover_signer(&signature, &bare_hash),
+        signature,
+    })
+}
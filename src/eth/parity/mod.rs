@@ -0,0 +1,2 @@
+pub mod trace;
+pub mod aura;
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use web3::api::Namespace;
+use web3::helpers::{CallFuture};
+use web3::types::{U256,Address,Transaction};
+use web3::Transport;
+use rustc_hex::{FromHexError};
+
+use super::super::types::*;
+
+/// `Trace` namespace, available in parity, openethereum, erigon and nethermind
+#[derive(Debug, Clone)]
+pub struct Trace<T> {
+    transport: T,
+}
+
+/// A transport for trace_ calls
+impl<T: Transport> Namespace<T> for Trace<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        Trace { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+/// The action of a trace, the fields depend on the trace type
+#[derive(Clone,Debug,Default,Serialize,Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceAction {
+    // call, create
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub call_type: Option<String>,
+    pub creation_method: Option<String>,
+    pub gas: Option<String>,
+    pub input: Option<String>,
+    pub init: Option<String>,
+    pub value: Option<String>,
+    // suicide
+    pub address: Option<String>,
+    pub balance: Option<String>,
+    pub refund_address: Option<String>,
+}
+
+/// The result of a call or create trace
+#[derive(Clone,Debug,Default,Serialize,Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceResult {
+    pub gas_used: Option<String>,
+    pub output: Option<String>,
+    pub address: Option<String>,
+}
+
+/// Entry returned by trace_transaction and trace_block
+#[derive(Clone,Debug,Serialize,Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceEntry {
+    #[serde(rename = "type")]
+    pub op: String,
+    pub action: TraceAction,
+    pub result: Option<TraceResult>,
+    pub error: Option<String>,
+    pub trace_address: Vec<usize>,
+    pub transaction_position: Option<u64>,
+}
+
+/// Parse optional hex address
+fn opthex_to_addr(s: &Option<String>) -> Result<Option<Address>,FromHexError> {
+    match s {
+        Some(s) if !s.is_empty() => Ok(Some(hex_to_addr(s)?)),
+        _ => Ok(None),
+    }
+}
+
+/// Parse optional hex quantity
+fn opthex_to_u256(s: &Option<String>) -> Result<U256,FromHexError> {
+    match s {
+        Some(s) => hex_to_u256(s),
+        None => Ok(U256::default()),
+    }
+}
+
+/// Parse optional hex data
+fn opthex_to_vec(s: &Option<String>) -> Result<Vec<u8>,FromHexError> {
+    match s {
+        Some(s) => hex_to_vec(s),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Parse the traces of a transaction and return a vector of InternalTx, in the
+///   same depth-first order that debug_traceTransaction callTracer
+pub fn parse_traces(traces: &[TraceEntry]) -> Result<Vec<InternalTx>,FromHexError> {
+    let mut itxs = Vec::new();
+    let mut itx_nos : HashMap<&[usize],u64> = HashMap::new();
+
+    for trace in traces {
+        // the root trace is the transaction itself
+        if trace.trace_address.is_empty() {
+            continue;
+        }
+        let depth = trace.trace_address.len();
+        let parent = itx_nos.get(&trace.trace_address[..depth-1]).cloned().unwrap_or(0);
+        let action = &trace.action;
+        let result = trace.result.clone().unwrap_or_default();
+
+        let itx = match trace.op.as_str() {
+            "create" => InternalTx {
+                from      : opthex_to_addr(&action.from)?.unwrap_or_default(),
+                to        : None,
+                contract  : opthex_to_addr(&result.address)?,
+                input     : opthex_to_vec(&action.init)?,
+                value     : opthex_to_u256(&action.value)?,
+                call_type : action.creation_method.as_ref()
+                    .map(|m| m.to_uppercase())
+                    .unwrap_or_else(|| "CREATE".to_string()),
+                gas       : opthex_to_u256(&action.gas)?,
+                gas_used  : opthex_to_u256(&result.gas_used)?,
+                output    : Vec::new(),
+                depth     : depth as u32,
+                parent,
+                error     : trace.error.clone(),
+            },
+            "suicide" => InternalTx {
+                from      : opthex_to_addr(&action.address)?.unwrap_or_default(),
+                to        : opthex_to_addr(&action.refund_address)?,
+                contract  : None,
+                input     : Vec::new(),
+                value     : opthex_to_u256(&action.balance)?,
+                call_type : "SELFDESTRUCT".to_string(),
+                depth     : depth as u32,
+                parent,
+                error     : trace.error.clone(),
+                ..Default::default()
+            },
+            _ => InternalTx {
+                from      : opthex_to_addr(&action.from)?.unwrap_or_default(),
+                to        : opthex_to_addr(&action.to)?,
+                contract  : None,
+                input     : opthex_to_vec(&action.input)?,
+                value     : opthex_to_u256(&action.value)?,
+                call_type : action.call_type.as_ref()
+                    .map(|t| t.to_uppercase())
+                    .unwrap_or_else(|| "CALL".to_string()),
+                gas       : opthex_to_u256(&action.gas)?,
+                gas_used  : opthex_to_u256(&result.gas_used)?,
+                output    : opthex_to_vec(&result.output)?,
+                depth     : depth as u32,
+                parent,
+                error     : trace.error.clone(),
+            },
+        };
+
+        itxs.push(itx);
+        itx_nos.insert(&trace.trace_address, itxs.len() as u64);
+    }
+    Ok(itxs)
+}
+
+/// Split the traces of a block by transaction, rewards are discarded
+pub fn parse_block_traces(traces: &[TraceEntry], tx_count: usize) -> Result<Vec<Vec<InternalTx>>,FromHexError> {
+    let mut per_tx : Vec<Vec<TraceEntry>> = vec![Vec::new(); tx_count];
+    for trace in traces {
+        if let Some(pos) = trace.transaction_position {
+            if let Some(tx_traces) = per_tx.get_mut(pos as usize) {
+                tx_traces.push(trace.clone());
+            }
+        }
+    }
+    per_tx.iter().map(|traces| parse_traces(traces)).collect()
+}
+
+impl<T: Transport> Trace<T> {
+
+    /// Retrieve the traces of a transaction by calling trace_transaction
+    pub fn transaction_traces(&self, tx: &Transaction) -> CallFuture<Vec<TraceEntry>, T::Out> {
+        CallFuture::new(
+            self.transport.execute(
+                "trace_transaction",
+                vec![web3::helpers::serialize(&tx.hash)]
+        ))
+    }
+}
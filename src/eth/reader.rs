@@ -1,42 +1,88 @@
 use state::*;
+use std::cell::Cell;
 use std::collections::HashMap;
+use std::sync::Arc;
 use web3::futures::Future;
+use web3::helpers;
 use web3::types::{
-    Address, Block, BlockId, BlockNumber, Bytes, Transaction, TransactionId, TransactionReceipt,
-    H256, U256,
+    Address, Block, BlockId, BlockNumber, Bytes, CallRequest, Transaction, TransactionId,
+    TransactionReceipt, H256, U256,
 };
+use web3::{BatchTransport, ErrorKind, Transport};
 
 use super::error::Result;
+use super::failover::is_transient;
 use super::types::*;
+use super::ext::EthExt;
+use super::txpool::{PoolTx, TxPool, TxPoolContent};
 
-use super::super::eth::geth;
+use super::super::eth::{geth, parity};
 use super::super::state::GlobalState;
 
 pub struct BlockchainReader<'a> {
-    wc: Web3Client,
+    wc: Option<Web3Client>,
+    stale: Cell<bool>,
     pub ge: &'a GlobalState,
 }
 
 /// Blockchain reader provides an unified way to access to the blockchain data 
 impl<'a> BlockchainReader<'a> {
     pub fn new(ge: &'a GlobalState) -> Self {
-        let wc = ge.new_web3client();
-        BlockchainReader { wc, ge }
+        let wc = ge.try_new_web3client();
+        BlockchainReader { wc, ge, stale: Cell::new(false) }
     }
 
-    /// retrieve the current block
+    /// check if the node was not available, so the data could be stale
+    pub fn is_stale(&self) -> bool {
+        self.stale.get()
+    }
+
+    /// call the node, returning None if it is not available
+    fn rpc<T,F>(&self, f: F) -> Result<Option<T>>
+    where
+        F: FnOnce(&Web3Client) -> web3::Result<T>
+    {
+        let result = match &self.wc {
+            Some(wc) => f(wc),
+            None => {
+                self.stale.set(true);
+                return Ok(None);
+            }
+        };
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(ref err) if is_transient(err) => {
+                self.stale.set(true);
+                Ok(None)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// retrieve the current block, or the last indexed one if the node is not available
     pub fn current_block_number(&self) -> Result<u64>{
-        Ok(self.wc.web3.eth().block_number().wait()?.low_u64())
+        if let Some(blockno) = self.rpc(|wc| wc.web3.eth().block_number().wait())? {
+            Ok(blockno.low_u64())
+        } else {
+            Ok(self.ge.db.get_next_block_to_scan()?.unwrap_or(1).saturating_sub(1))
+        }
     }
 
-    /// retrieve the current balance for an address
-    pub fn current_balance(&self, addr: &Address) -> Result<U256>{
-        Ok(self.wc.web3.eth().balance(*addr, None).wait()?)
+    /// check if a block has not reached the scan confirmations yet, so it is
+    ///   not indexed and can be reorganized
+    pub fn is_unconfirmed(&self, blockno: u64, last_blockno: u64) -> bool {
+        // without node, last_blockno is the last indexed block
+        !self.is_stale() && blockno + self.ge.cfg.scan_confirmations.unwrap_or(0) > last_blockno
     }
 
-    /// retrieve the current code for an address
-    pub fn current_code(&self, addr: &Address) -> Result<Bytes>{
-        Ok(self.wc.web3.eth().code(*addr, None).wait()?)
+    /// retrieve the current balance for an address, None if the node is not available
+    pub fn current_balance(&self, addr: &Address) -> Result<Option<U256>>{
+        self.rpc(|wc| wc.web3.eth().balance(*addr, None).wait())
+    }
+
+    /// retrieve the current code for an address, None if the node is not available
+    pub fn current_code(&self, addr: &Address) -> Result<Option<Bytes>>{
+        self.rpc(|wc| wc.web3.eth().code(*addr, None).wait())
     }
 
     /// retrieve a block
@@ -45,11 +91,7 @@ impl<'a> BlockchainReader<'a> {
             Ok(Some(blk))
         } else {
             let blockid = BlockId::Number(BlockNumber::Number(blockno));
-            if let Some(blk) = self.wc.web3.eth().block(blockid).wait()? {
-                Ok(Some(blk))
-            } else {
-                Ok(None)
-            }
+            Ok(self.rpc(|wc| wc.web3.eth().block(blockid).wait())?.and_then(|blk| blk))
         }
     }
     /// retrieve a block with its transactions
@@ -66,12 +108,107 @@ impl<'a> BlockchainReader<'a> {
             })))
         } else {
             let blockid = BlockId::Number(BlockNumber::Number(blockno));
-            if let Some(blk) = self.wc.web3.eth().block_with_txs(blockid).wait()? {
-                Ok(Some(blk))
-            } else {
-                Ok(None)
+            Ok(self.rpc(|wc| wc.web3.eth().block_with_txs(blockid).wait())?.and_then(|blk| blk))
+        }
+    }
+
+    /// retrieve the post-merge fields and withdrawals of a block
+    pub fn block_ext(&self, blockno: u64) -> Result<Option<BlockExt>>{
+        if let Some(ext) = self.ge.db.get_block_ext(blockno)? {
+            Ok(Some(ext))
+        } else if self.ge.db.get_block_hash(blockno)?.is_some() {
+            // indexed blocks without the fields are before london
+            Ok(None)
+        } else {
+            Ok(self.rpc(|wc| {
+                let pos : geth::pos::Pos<_> = wc.web3.api();
+                pos.block_ext(blockno).wait()
+            })?.and_then(|ext| ext))
+        }
+    }
+
+    /// retrieve the uncles of a block
+    pub fn uncles<T>(&self, block: &Block<T>) -> Result<Vec<Block<H256>>>{
+        let blockno = block.number.unwrap().low_u64();
+        let uncles = self.ge.db.get_uncles(blockno)?;
+        if uncles.len() == block.uncles.len() {
+            return Ok(uncles);
+        }
+        let mut uncles = Vec::new();
+        for index in 0..block.uncles.len() as u64 {
+            let uncle = self.rpc(|wc| {
+                let ext : EthExt<_> = wc.web3.api();
+                ext.uncle(blockno, index).wait()
+            })?;
+            if let Some(Some(uncle)) = uncle {
+                uncles.push(uncle);
+            }
+        }
+        Ok(uncles)
+    }
+
+    /// retrieve the typed fields of a transaction
+    pub fn tx_ext(&self, txhash: &H256) -> Result<Option<TxExt>>{
+        if let Some(ext) = self.ge.db.get_tx_ext(txhash)? {
+            Ok(Some(ext))
+        } else if self.ge.db.get_tx(txhash)?.is_some() {
+            // stored transactions without typed fields are before berlin
+            Ok(Some(TxExt::default()))
+        } else {
+            Ok(self.rpc(|wc| {
+                let ext : EthExt<_> = wc.web3.api();
+                let tx_ext = ext.tx_ext(txhash).wait()?;
+                let receipt_ext = ext.receipt_ext(txhash).wait()?;
+                Ok(tx_ext.map(|tx_ext| tx_ext.with_receipt(receipt_ext.unwrap_or_default())))
+            })?.and_then(|ext| ext))
+        }
+    }
+
+    /// retrieve the transactions in the pool, only the ones sent by addr if
+    ///   set. None if the node is not available
+    pub fn pool_content(&self, addr: Option<&Address>) -> Result<Option<TxPoolContent>>{
+        self.rpc(|wc| {
+            // nodes without the txpool_ api have an empty pool
+            let txpool : TxPool<_> = wc.web3.api();
+            let content = match addr {
+                Some(addr) => txpool.content_from(*addr).wait()
+                    .map(|content| content.into_content(*addr)),
+                None => txpool.content().wait(),
+            };
+            content.or_else(|err| match err.kind() {
+                ErrorKind::Rpc(_) => Ok(TxPoolContent::default()),
+                _ => Err(err),
+            })
+        })
+    }
+
+    /// get the transactions of the pool content that skips and takes the
+    ///   ones in a page, with the nonce gaps of the queued ones
+    pub fn pool_txs(&self, content: TxPoolContent, skip: usize, take: usize) -> Result<Vec<PoolTx>>{
+
+        // the account nonces of the senders in the page, to find the missing
+        //   ones before the queued txs
+        let senders = content.queued_senders(skip, take);
+        let mut nonces = HashMap::new();
+        if !senders.is_empty() {
+            let counts = self.rpc(|wc| {
+                let transport = wc.web3.transport();
+                let requests : Vec<_> = senders.iter()
+                    .map(|sender| transport.prepare("eth_getTransactionCount", vec![
+                        helpers::serialize(sender),
+                        helpers::serialize(&BlockNumber::Latest),
+                    ]))
+                    .collect();
+                transport.send_batch(requests).wait()
+            })?.unwrap_or_default();
+            for (sender, count) in senders.iter().zip(counts) {
+                if let Some(nonce) = count.ok().and_then(|count| serde_json::from_value::<U256>(count).ok()) {
+                    nonces.insert(*sender, nonce.low_u64());
+                }
             }
         }
+
+        Ok(content.into_txs(&nonces).into_iter().skip(skip).take(take).collect())
     }
 
     /// retrieve a transaction
@@ -82,16 +219,15 @@ impl<'a> BlockchainReader<'a> {
         let mut tx = self.ge.db.get_tx(&txhash)?;
         if tx.is_none() {
             tx = self
-                .wc
-                .web3
-                .eth()
-                .transaction(TransactionId::Hash(txhash))
-                .wait()?;
+                .rpc(|wc| wc.web3.eth().transaction(TransactionId::Hash(txhash)).wait())?
+                .and_then(|tx| tx);
         }
         if let Some(tx) = tx {
             let mut receipt = self.ge.db.get_receipt(&txhash)?;
             if receipt.is_none() {
-                receipt = self.wc.web3.eth().transaction_receipt(txhash).wait()?
+                receipt = self
+                    .rpc(|wc| wc.web3.eth().transaction_receipt(txhash).wait())?
+                    .and_then(|receipt| receipt);
             }
             Ok(Some((tx, receipt)))
         } else {
@@ -99,15 +235,105 @@ impl<'a> BlockchainReader<'a> {
         }
     }
 
+    /// retrieve the data returned by a failed transaction, from the callTracer output
+    ///   if internal transactions are enabled, or replaying it at the parent block
+    pub fn revert_data(&self, tx: &Transaction) -> Result<Option<Vec<u8>>> {
+        if self.ge.cfg.web3_itx && !self.ge.cfg.trace_api() {
+            let trace = self.rpc(|wc| {
+                let dbg : geth::web3::Debug<_> = wc.web3.api();
+                dbg.internal_txs(&tx).wait()
+            })?;
+            if let Some(output) = trace.and_then(|trace| trace.output) {
+                return Ok(Some(hex_to_vec(&output)?));
+            }
+        }
+
+        let (wc, to, blockno) = match (&self.wc, tx.to, tx.block_number) {
+            (Some(wc), Some(to), Some(blockno)) => (wc, to, blockno.low_u64()),
+            _ => return Ok(None),
+        };
+        let req = CallRequest {
+            from : Some(tx.from),
+            to,
+            gas : Some(tx.gas),
+            gas_price : Some(tx.gas_price),
+            value : Some(tx.value),
+            data : Some(tx.input.clone()),
+        };
+        let parent = BlockNumber::Number(blockno.saturating_sub(1));
+        match wc.web3.eth().call(req, Some(parent)).wait() {
+            // the replay does not include the previous transactions in the block,
+            //   so it could succeed
+            Ok(_) => Ok(None),
+            Err(err) => {
+                if is_transient(&err) {
+                    self.stale.set(true);
+                }
+                // the revert data is in the error data, prefixed in some clients
+                let data = match err.kind() {
+                    ErrorKind::Rpc(err) => err.data.as_ref()
+                        .and_then(|data| data.as_str())
+                        .map(|data| data.trim_start_matches("Reverted ").to_string()),
+                    _ => None,
+                };
+                Ok(data.and_then(|data| hex_to_vec(&data).ok()))
+            }
+        }
+    }
+
+    /// retrieve the state changes made by a transaction, None if the node
+    ///   is not available
+    pub fn state_diff(&self, txhash: H256) -> Result<Option<geth::web3::DbgStateDiff>> {
+        self.rpc(|wc| {
+            let dbg : geth::web3::Debug<_> = wc.web3.api();
+            dbg.state_diff(&txhash).wait()
+        })
+    }
+
+    /// retrieve the opcode-level trace of a transaction, None if the node
+    ///   is not available. The last one is kept, since it is rendered by pages
+    pub fn struct_logs(&self, txhash: H256) -> Result<Option<Arc<geth::web3::DbgStructLogs>>> {
+        if let Some((hash, trace)) = &*self.ge.last_trace.lock().unwrap() {
+            if *hash == txhash {
+                return Ok(Some(trace.clone()));
+            }
+        }
+        let trace = self.rpc(|wc| {
+            let dbg : geth::web3::Debug<_> = wc.web3.api();
+            dbg.struct_logs(&txhash).wait()
+        })?.map(Arc::new);
+        if let Some(trace) = &trace {
+            *self.ge.last_trace.lock().unwrap() = Some((txhash, trace.clone()));
+        }
+        Ok(trace)
+    }
+
     /// retrieve an internal transaction
     pub fn itx(
         &self,
         tx: &Transaction
     ) -> Result<Vec<InternalTx>> {
         let mut itxs : Vec<InternalTx> = self.ge.db.iter_itxs(&tx.hash).map(|(_,t)| t).collect();
+        // internal transactions are iterated from the last one
+        itxs.reverse();
         if itxs.is_empty() && self.ge.cfg.web3_itx {
-            let dbg : geth::web3::Debug<_> = self.wc.web3.api();
-            itxs = dbg.internal_txs(&tx).wait()?.parse()?;
+            if self.ge.cfg.trace_api() {
+                let traces = self.rpc(|wc| {
+                    let trace : parity::trace::Trace<_> = wc.web3.api();
+                    trace.transaction_traces(&tx).wait()
+                })?;
+                if let Some(traces) = traces {
+                    itxs = parity::trace::parse_traces(&traces)?;
+                }
+            } else {
+                let dbg = self.rpc(|wc| {
+                    let dbg : geth::web3::Debug<_> = wc.web3.api();
+                    dbg.internal_txs(&tx).wait()
+                })?;
+                if let Some(dbg) = dbg {
+                    itxs = dbg.parse()?;
+                }
+            }
         }
         Ok(itxs)
     }
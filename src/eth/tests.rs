@@ -0,0 +1,778 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use ethkey::{sign, KeyPair, Secret};
+    use keccak_hash::keccak;
+    use rlp::RlpStream;
+    use web3::types::{Address, Block, Bytes, Transaction, H256, U256};
+
+    use super::super::types::*;
+    use super::super::contract::{ContractParser, RevertInfo, SourceMap};
+    use super::super::besu::bft::{parse_bft_committers, parse_bft_extra};
+    use super::super::genesis::{read_genesis_alloc, read_genesis_forks, GenesisForks};
+    use super::super::geth::ethash::RewardSchedule;
+    use super::super::geth::clique::{seal_hash, SealViolation, SignerChange, Snapshot};
+    use super::super::geth::web3::DbgInternalTxs;
+    use super::super::parity::aura::parse_aura_seal;
+    use super::super::parity::trace::{parse_block_traces, parse_traces, TraceEntry};
+    use super::super::txpool::{PoolStatus, TxPoolContentFrom};
+    use super::super::super::fixtures::block_with;
+
+    #[test]
+    fn test_parse_calltracer_failed_nested_create() {
+        // the failed CREATE has no `to`, the CREATE2 is nested in the CALL
+        let trace : DbgInternalTxs = serde_json::from_str(r#"{
+            "type": "CALL",
+            "from": "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+            "to": "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+            "input": "0x",
+            "output": "0x",
+            "calls": [
+                {
+                    "type": "CREATE",
+                    "from": "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+                    "input": "0x6080",
+                    "value": "0x0",
+                    "gas": "0x1000",
+                    "gasUsed": "0x1000",
+                    "error": "out of gas"
+                },
+                {
+                    "type": "CALL",
+                    "from": "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+                    "to": "0xcccccccccccccccccccccccccccccccccccccccc",
+                    "input": "0x",
+                    "value": "0x1",
+                    "calls": [
+                        {
+                            "type": "CREATE2",
+                            "from": "0xcccccccccccccccccccccccccccccccccccccccc",
+                            "to": "0xdddddddddddddddddddddddddddddddddddddddd",
+                            "input": "0x6080",
+                            "value": "0x0"
+                        }
+                    ]
+                }
+            ]
+        }"#).unwrap();
+
+        let b = hex_to_addr("0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb").unwrap();
+        let c = hex_to_addr("0xcccccccccccccccccccccccccccccccccccccccc").unwrap();
+        let d = hex_to_addr("0xdddddddddddddddddddddddddddddddddddddddd").unwrap();
+
+        let itxs = trace.parse().unwrap();
+        assert_eq!(3, itxs.len());
+
+        assert_eq!(b, itxs[0].from);
+        assert_eq!(None, itxs[0].to);
+        assert_eq!(None, itxs[0].contract);
+        assert_eq!("CREATE", itxs[0].call_type);
+        assert_eq!(Some("out of gas".to_string()), itxs[0].error);
+        assert_eq!((1, 0), (itxs[0].depth, itxs[0].parent));
+
+        assert_eq!(Some(c), itxs[1].to);
+        assert_eq!((1, 0), (itxs[1].depth, itxs[1].parent));
+
+        assert_eq!(c, itxs[2].from);
+        assert_eq!(None, itxs[2].to);
+        assert_eq!(Some(d), itxs[2].contract);
+        assert_eq!((2, 2), (itxs[2].depth, itxs[2].parent));
+    }
+
+    /// traces of a tx to B with a failed create, and a delegatecall to C
+    ///   that selfdestructs sending the funds to D, as returned by trace_block
+    const TX0_TRACES : &str = r#"[
+        {
+            "action": {
+                "callType": "call",
+                "from": "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                "to": "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+                "gas": "0x5208",
+                "input": "0x",
+                "value": "0x0"
+            },
+            "result": { "gasUsed": "0x5208", "output": "0x" },
+            "subtraces": 2,
+            "traceAddress": [],
+            "transactionPosition": 0,
+            "type": "call"
+        },
+        {
+            "action": {
+                "from": "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+                "gas": "0x1000",
+                "init": "0x6080",
+                "value": "0x0"
+            },
+            "error": "Out of gas",
+            "result": null,
+            "subtraces": 0,
+            "traceAddress": [0],
+            "transactionPosition": 0,
+            "type": "create"
+        },
+        {
+            "action": {
+                "callType": "delegatecall",
+                "from": "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+                "to": "0xcccccccccccccccccccccccccccccccccccccccc",
+                "gas": "0x2000",
+                "input": "0x41c0e1b5",
+                "value": "0x0"
+            },
+            "result": { "gasUsed": "0x1388", "output": "0x01" },
+            "subtraces": 1,
+            "traceAddress": [1],
+            "transactionPosition": 0,
+            "type": "call"
+        },
+        {
+            "action": {
+                "address": "0xcccccccccccccccccccccccccccccccccccccccc",
+                "balance": "0x5",
+                "refundAddress": "0xdddddddddddddddddddddddddddddddddddddddd"
+            },
+            "result": null,
+            "subtraces": 0,
+            "traceAddress": [1, 0],
+            "transactionPosition": 0,
+            "type": "suicide"
+        }
+    ]"#;
+
+    /// a tx with a nested create, and the block reward
+    const TX1_AND_REWARD_TRACES : &str = r#"[
+        {
+            "action": {
+                "callType": "call",
+                "from": "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                "to": "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+                "gas": "0x5208",
+                "input": "0x",
+                "value": "0x0"
+            },
+            "result": { "gasUsed": "0x5208", "output": "0x" },
+            "subtraces": 1,
+            "traceAddress": [],
+            "transactionPosition": 1,
+            "type": "call"
+        },
+        {
+            "action": {
+                "creationMethod": "create2",
+                "from": "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+                "gas": "0x1000",
+                "init": "0x6080",
+                "value": "0x0"
+            },
+            "result": {
+                "address": "0xdddddddddddddddddddddddddddddddddddddddd",
+                "code": "0x",
+                "gasUsed": "0x800"
+            },
+            "subtraces": 0,
+            "traceAddress": [0],
+            "transactionPosition": 1,
+            "type": "create"
+        },
+        {
+            "action": {
+                "author": "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa",
+                "rewardType": "block",
+                "value": "0x1bc16d674ec80000"
+            },
+            "result": null,
+            "subtraces": 0,
+            "traceAddress": [],
+            "type": "reward"
+        }
+    ]"#;
+
+    #[test]
+    fn test_parse_traces() {
+        let traces : Vec<TraceEntry> = serde_json::from_str(TX0_TRACES).unwrap();
+        let b = hex_to_addr("0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb").unwrap();
+        let c = hex_to_addr("0xcccccccccccccccccccccccccccccccccccccccc").unwrap();
+        let d = hex_to_addr("0xdddddddddddddddddddddddddddddddddddddddd").unwrap();
+
+        let itxs = parse_traces(&traces).unwrap();
+        assert_eq!(3, itxs.len());
+
+        // the failed create has no contract
+        assert_eq!("CREATE", itxs[0].call_type);
+        assert_eq!((b, None, None), (itxs[0].from, itxs[0].to, itxs[0].contract));
+        assert_eq!(Some("Out of gas".to_string()), itxs[0].error);
+        assert_eq!((1, 0), (itxs[0].depth, itxs[0].parent));
+
+        assert_eq!("DELEGATECALL", itxs[1].call_type);
+        assert_eq!((b, Some(c)), (itxs[1].from, itxs[1].to));
+        assert_eq!(vec![1u8], itxs[1].output);
+        assert_eq!((1, 0), (itxs[1].depth, itxs[1].parent));
+
+        // the selfdestruct is nested in the delegatecall, that is the 2nd itx
+        assert_eq!("SELFDESTRUCT", itxs[2].call_type);
+        assert_eq!((c, Some(d)), (itxs[2].from, itxs[2].to));
+        assert_eq!(5, itxs[2].value.low_u64());
+        assert_eq!((2, 2), (itxs[2].depth, itxs[2].parent));
+    }
+
+    #[test]
+    fn test_parse_block_traces() {
+        let mut traces : Vec<TraceEntry> = serde_json::from_str(TX0_TRACES).unwrap();
+        traces.extend(serde_json::from_str::<Vec<TraceEntry>>(TX1_AND_REWARD_TRACES).unwrap());
+        let d = hex_to_addr("0xdddddddddddddddddddddddddddddddddddddddd").unwrap();
+
+        // the reward has no transaction position and it is discarded
+        let itxs = parse_block_traces(&traces, 2).unwrap();
+        assert_eq!(2, itxs.len());
+        assert_eq!(3, itxs[0].len());
+        assert_eq!(1, itxs[1].len());
+        assert_eq!("CREATE2", itxs[1][0].call_type);
+        assert_eq!(Some(d), itxs[1][0].contract);
+        assert_eq!((1, 0), (itxs[1][0].depth, itxs[1][0].parent));
+
+        // transactions without traces have no internal transactions
+        let itxs = parse_block_traces(&traces, 3).unwrap();
+        assert!(itxs[2].is_empty());
+    }
+
+    /// keys of clique signers, sorted by address
+    fn signer_keys(count: u8) -> Vec<KeyPair> {
+        let mut keys : Vec<KeyPair> = (1..=count)
+            .map(|n| KeyPair::from_secret(Secret::from([n;32])).unwrap())
+            .collect();
+        keys.sort_by_key(|key| key.address());
+        keys
+    }
+
+    /// a header without transactions, the seal is added by the callers
+    fn test_block(number: u64, difficulty: u64, author: Address, nonce: &str, extra: Vec<u8>) -> Block<H256> {
+        block_with(number, json!({
+            "hash": H256::zero(),
+            "parentHash": H256::zero(),
+            "miner": author,
+            "extraData": Bytes(extra),
+            "timestamp": format!("0x{:x}", 1_546_300_800 + number * 15),
+            "difficulty": format!("0x{:x}", difficulty),
+            "mixHash": H256::zero(),
+            "nonce": nonce,
+        }))
+    }
+
+    /// a clique header sealed by the key, voting to add or remove the target.
+    ///   checkpoint headers list the signers in the extra data
+    fn clique_block(key: &KeyPair, number: u64, difficulty: u64, vote: Option<(Address,bool)>, signers: &[Address]) -> Block<H256> {
+        let mut extra = vec![0u8;32];
+        for signer in signers {
+            extra.extend_from_slice(signer);
+        }
+        extra.extend_from_slice(&[0u8;65]);
+        let (author, nonce) = match vote {
+            Some((target, true)) => (target, "0xffffffffffffffff"),
+            Some((target, false)) => (target, "0x0000000000000000"),
+            None => (Address::zero(), "0x0000000000000000"),
+        };
+        let mut block = test_block(number, difficulty, author, nonce, extra);
+
+        let signature = sign(key.secret(), &seal_hash(&block).unwrap()).unwrap();
+        let len = block.extra_data.0.len();
+        block.extra_data.0[len-65..].copy_from_slice(&(*signature)[..]);
+        block
+    }
+
+    /// a snapshot after the block with the signers
+    fn new_snapshot(number: u64, signers: &[Address]) -> Snapshot {
+        Snapshot { number, signers: signers.to_vec(), votes: Vec::new(), recents: Vec::new() }
+    }
+
+    #[test]
+    fn test_clique_apply_vote_tally() {
+        let keys = signer_keys(4);
+        let (a, b, c) = (&keys[0], &keys[1], &keys[2]);
+        let d = keys[3].address();
+        let mut snapshot = new_snapshot(0, &[a.address(), b.address(), c.address()]);
+
+        // a repeated vote replaces the previous one
+        assert!(snapshot.apply(&clique_block(a, 1, 1, Some((d,true)), &[]), 30000).is_empty());
+        assert!(snapshot.apply(&clique_block(a, 2, 1, Some((d,true)), &[]), 30000).is_empty());
+        assert_eq!(1, snapshot.votes.len());
+        assert_eq!(vec![(d, true, vec![a.address()])], snapshot.proposals());
+
+        // votes on what is already decided are not counted
+        assert!(snapshot.apply(&clique_block(c, 3, 1, Some((b.address(),true)), &[]), 30000).is_empty());
+        assert_eq!(1, snapshot.votes.len());
+
+        // the majority adds the signer, removing its votes
+        assert_eq!(vec![SignerChange { signer: d, added: true }],
+            snapshot.apply(&clique_block(b, 4, 1, Some((d,true)), &[]), 30000));
+        assert!(snapshot.is_signer(&d));
+        assert_eq!(4, snapshot.signers.len());
+        assert!(snapshot.votes.is_empty());
+        assert_eq!(4, snapshot.number);
+    }
+
+    #[test]
+    fn test_clique_apply_drop_signer() {
+        let keys = signer_keys(4);
+        let (a, b, c) = (&keys[0], &keys[1], &keys[2]);
+        let d = keys[3].address();
+        let mut snapshot = new_snapshot(0, &[a.address(), b.address(), c.address()]);
+
+        // the votes of the dropped signer are discarded
+        snapshot.apply(&clique_block(c, 1, 1, Some((d,true)), &[]), 30000);
+        snapshot.apply(&clique_block(a, 2, 1, Some((c.address(),false)), &[]), 30000);
+        assert_eq!(2, snapshot.votes.len());
+        assert_eq!(vec![SignerChange { signer: c.address(), added: false }],
+            snapshot.apply(&clique_block(b, 3, 1, Some((c.address(),false)), &[]), 30000));
+        assert_eq!(vec![a.address(), b.address()], snapshot.signers);
+        assert!(snapshot.votes.is_empty());
+    }
+
+    #[test]
+    fn test_clique_apply_epoch_reset() {
+        let keys = signer_keys(3);
+        let (a, b, c) = (&keys[0], &keys[1], &keys[2]);
+        let mut snapshot = new_snapshot(2, &[a.address(), b.address()]);
+
+        snapshot.apply(&clique_block(a, 3, 1, Some((c.address(),true)), &[]), 4);
+        assert_eq!(1, snapshot.votes.len());
+
+        // the checkpoint lists the signers and drops the pending votes
+        let changes = snapshot.apply(&clique_block(a, 4, 1, None, &[a.address(), c.address()]), 4);
+        assert_eq!(vec![
+            SignerChange { signer: c.address(), added: true },
+            SignerChange { signer: b.address(), added: false },
+        ], changes);
+        assert_eq!(vec![a.address(), c.address()], snapshot.signers);
+        assert!(snapshot.votes.is_empty());
+    }
+
+    #[test]
+    fn test_clique_verify_seal() {
+        let keys = signer_keys(4);
+        let (a, b, c, d) = (&keys[0], &keys[1], &keys[2], &keys[3]);
+        let mut snapshot = new_snapshot(2, &[a.address(), b.address(), c.address()]);
+        snapshot.recents = vec![(2, b.address())];
+
+        // block 3 is the turn of a, the signers at 3 % 3
+        assert_eq!(None, snapshot.verify_seal(&clique_block(a, 3, 2, None, &[])));
+        assert_eq!(None, snapshot.verify_seal(&clique_block(c, 3, 1, None, &[])));
+
+        assert_eq!(Some(SealViolation::Unauthorized(d.address())),
+            snapshot.verify_seal(&clique_block(d, 3, 1, None, &[])));
+        assert_eq!(Some(SealViolation::RecentlySigned(b.address(), 2)),
+            snapshot.verify_seal(&clique_block(b, 3, 1, None, &[])));
+        assert_eq!(Some(SealViolation::WrongDifficulty(a.address())),
+            snapshot.verify_seal(&clique_block(a, 3, 1, None, &[])));
+        assert_eq!(Some(SealViolation::WrongDifficulty(c.address())),
+            snapshot.verify_seal(&clique_block(c, 3, 2, None, &[])));
+
+        let mut unsealed = clique_block(a, 3, 2, None, &[]);
+        unsealed.extra_data.0.truncate(32);
+        assert_eq!(Some(SealViolation::InvalidSeal), snapshot.verify_seal(&unsealed));
+    }
+
+    #[test]
+    fn test_clique_apply_recents() {
+        let keys = signer_keys(4);
+        let (a, b, c, d) = (&keys[0], &keys[1], &keys[2], &keys[3]);
+        let mut snapshot = new_snapshot(0, &[a.address(), b.address(), c.address()]);
+
+        // with 3 signers, a signer can seal again after 2 blocks
+        snapshot.apply(&clique_block(a, 1, 1, None, &[]), 30000);
+        snapshot.apply(&clique_block(b, 2, 1, None, &[]), 30000);
+        assert_eq!(vec![(1, a.address()), (2, b.address())], snapshot.recents);
+        snapshot.apply(&clique_block(c, 3, 1, None, &[]), 30000);
+        assert_eq!(vec![(2, b.address()), (3, c.address())], snapshot.recents);
+        snapshot.apply(&clique_block(a, 4, 1, None, &[]), 30000);
+        assert_eq!(vec![(3, c.address()), (4, a.address())], snapshot.recents);
+
+        // dropping a signer from 4 to 3 shrinks the limit from 3 to 2 blocks
+        let mut snapshot = new_snapshot(0, &[a.address(), b.address(), c.address(), d.address()]);
+        snapshot.apply(&clique_block(a, 1, 1, Some((d.address(),false)), &[]), 30000);
+        snapshot.apply(&clique_block(b, 2, 1, Some((d.address(),false)), &[]), 30000);
+        assert_eq!(vec![(1, a.address()), (2, b.address())], snapshot.recents);
+        assert_eq!(vec![SignerChange { signer: d.address(), added: false }],
+            snapshot.apply(&clique_block(c, 3, 1, Some((d.address(),false)), &[]), 30000));
+        assert_eq!(vec![(2, b.address()), (3, c.address())], snapshot.recents);
+    }
+
+    /// the pool content of a sender, as returned by txpool_contentFrom
+    fn pool_content(sender: Address, pending: &[u64], queued: &[u64]) -> TxPoolContentFrom {
+        let tx = |nonce: &u64| (nonce.to_string(), serde_json::from_value::<Transaction>(json!({
+            "hash": H256::from(*nonce),
+            "nonce": format!("0x{:x}", nonce),
+            "blockHash": null,
+            "blockNumber": null,
+            "transactionIndex": null,
+            "from": sender,
+            "to": Address::zero(),
+            "value": "0x0",
+            "gasPrice": "0x3b9aca00",
+            "gas": "0x5208",
+            "input": "0x",
+        })).unwrap());
+        serde_json::from_value(json!({
+            "pending": pending.iter().map(tx).collect::<HashMap<_,_>>(),
+            "queued": queued.iter().map(tx).collect::<HashMap<_,_>>(),
+        })).unwrap()
+    }
+
+    /// get the nonces and statuses of the pool transactions
+    fn pool_statuses(content: TxPoolContentFrom, sender: Address, nonces: &[(Address,u64)]) -> Vec<(u64,PoolStatus)> {
+        let nonces : HashMap<Address,u64> = nonces.iter().cloned().collect();
+        content.into_content(sender)
+            .into_txs(&nonces)
+            .into_iter()
+            .map(|ptx| (ptx.tx.nonce.low_u64(), ptx.status))
+            .collect()
+    }
+
+    #[test]
+    fn test_pool_pending_and_queued_with_gap() {
+        let sender = Address::from(1);
+        let content = pool_content(sender, &[4, 3], &[10, 7]);
+        assert_eq!(vec![
+            (3, PoolStatus::Pending),
+            (4, PoolStatus::Pending),
+            (7, PoolStatus::NonceGap(5, 6)),
+            (10, PoolStatus::NonceGap(8, 9)),
+        ], pool_statuses(content, sender, &[]));
+    }
+
+    #[test]
+    fn test_pool_queued_uses_account_nonce() {
+        let sender = Address::from(1);
+        assert_eq!(vec![(2, PoolStatus::NonceGap(0, 1))],
+            pool_statuses(pool_content(sender, &[], &[2]), sender, &[(sender, 0)]));
+
+        // without a gap the transaction is not executable for other reasons
+        assert_eq!(vec![(2, PoolStatus::Queued)],
+            pool_statuses(pool_content(sender, &[], &[2]), sender, &[(sender, 2)]));
+
+        // the gap is unknown without the account nonce
+        assert_eq!(vec![(2, PoolStatus::Queued)],
+            pool_statuses(pool_content(sender, &[], &[2]), sender, &[]));
+    }
+
+    #[test]
+    fn test_pool_consecutive_queued_after_gap() {
+        let sender = Address::from(1);
+        let content = pool_content(sender, &[3], &[5, 6, 7]);
+        assert_eq!(vec![
+            (3, PoolStatus::Pending),
+            (5, PoolStatus::NonceGap(4, 4)),
+            (6, PoolStatus::NonceGap(4, 4)),
+            (7, PoolStatus::NonceGap(4, 4)),
+        ], pool_statuses(content, sender, &[]));
+    }
+
+    #[test]
+    fn test_pool_queued_senders_of_page() {
+        let (a, b, c) = (Address::from(1), Address::from(2), Address::from(3));
+        let mut content = pool_content(a, &[], &[5, 6]).into_content(a);
+        for (sender, pending, queued) in vec![(b, vec![1], vec![3]), (c, vec![], vec![2, 4])] {
+            let other = pool_content(sender, &pending, &queued).into_content(sender);
+            content.pending.extend(other.pending);
+            content.queued.extend(other.queued);
+        }
+        assert_eq!(6, content.count());
+        assert_eq!(5, content.queued_count());
+
+        // the txs of a are 0-1, of b 2-3 and of c 4-5, b has a pending tx
+        assert_eq!(vec![a], content.queued_senders(0, 2));
+        assert_eq!(vec![a, c], content.queued_senders(1, 4));
+        assert!(content.queued_senders(2, 2).is_empty());
+        assert_eq!(vec![c], content.queued_senders(5, 25));
+        assert_eq!(vec![a, c], content.queued_senders(0, std::usize::MAX));
+    }
+
+    /// the hash of the header fields before the seal fields, with the extra
+    ///   data as signed by the sealers
+    fn bare_header_hash(block: &Block<H256>, extra: &[u8], with_mix_nonce: bool, base_fee: Option<U256>) -> H256 {
+        let len = 13 + if with_mix_nonce { 2 } else { 0 } + if base_fee.is_some() { 1 } else { 0 };
+        let mut stream = RlpStream::new_list(len);
+        stream
+            .append(&block.parent_hash)
+            .append(&block.uncles_hash)
+            .append(&block.author)
+            .append(&block.state_root)
+            .append(&block.transactions_root)
+            .append(&block.receipts_root)
+            .append(&block.logs_bloom)
+            .append(&block.difficulty)
+            .append(&block.number.unwrap())
+            .append(&block.gas_limit)
+            .append(&block.gas_used)
+            .append(&block.timestamp)
+            .append(&extra.to_vec());
+        if with_mix_nonce {
+            stream.append(&block.mix_hash.unwrap()).append(&block.nonce.unwrap());
+        }
+        if let Some(base_fee) = base_fee {
+            stream.append(&base_fee);
+        }
+        keccak(&stream.out())
+    }
+
+    /// the extraData of an IBFT 2.0 or QBFT block, RLP([vanity, validators,
+    ///   vote, round, seals]). Without seals the list has only 4 items
+    fn bft_extra(validators: &[Address], vote: Option<(Address,bool)>, round: &[u8], seals: Option<&[Vec<u8>]>) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(if seals.is_some() { 5 } else { 4 });
+        stream.append(&vec![0u8;32]);
+        stream.append_list::<Address, Address>(validators);
+        match vote {
+            Some((recipient, authorize)) => {
+                stream.begin_list(2)
+                    .append(&recipient)
+                    .append(&vec![if authorize { 0xffu8 } else { 0x00u8 }]);
+            }
+            None => {
+                stream.begin_list(0);
+            }
+        }
+        stream.append(&round.to_vec());
+        if let Some(seals) = seals {
+            stream.begin_list(seals.len());
+            for seal in seals {
+                stream.append(seal);
+            }
+        }
+        stream.out()
+    }
+
+    /// commit the block with the keys, returning the sealed extraData
+    fn bft_commit(block: &Block<H256>, unsealed: &[u8], keys: &[&KeyPair], base_fee: Option<U256>) -> Vec<Vec<u8>> {
+        let hash = bare_header_hash(block, unsealed, true, base_fee);
+        keys.iter()
+            .map(|key| (*sign(key.secret(), &hash).unwrap())[..].to_vec())
+            .collect()
+    }
+
+    #[test]
+    fn test_ibft2_committers() {
+        let keys = signer_keys(4);
+        let validators : Vec<Address> = keys[..3].iter().map(|key| key.address()).collect();
+        let vote = Some((keys[3].address(), true));
+        let round = [0, 0, 0, 2];
+        let mut block = test_block(100, 1, validators[0], "0x0000000000000000", Vec::new());
+
+        let unsealed = bft_extra(&validators, vote, &round, None);
+        let seals = bft_commit(&block, &unsealed, &[&keys[0], &keys[2]], None);
+        block.extra_data = Bytes(bft_extra(&validators, vote, &round, Some(&seals[..])));
+
+        let extra = parse_bft_extra(&block.extra_data.0, false).unwrap();
+        assert_eq!(validators, extra.validators);
+        assert_eq!(Some((keys[3].address(), true)), extra.vote.as_ref().map(|v| (v.recipient, v.authorize)));
+        assert_eq!(2, extra.round);
+        assert_eq!(2, extra.seals.len());
+        assert_eq!(vec![validators[0], validators[2]], parse_bft_committers(&block, None, &extra));
+    }
+
+    #[test]
+    fn test_qbft_committers_after_london() {
+        let keys = signer_keys(3);
+        let validators : Vec<Address> = keys.iter().map(|key| key.address()).collect();
+        let base_fee = U256::from(7);
+        let ext = BlockExt { base_fee_per_gas: Some(base_fee), ..Default::default() };
+        let mut block = test_block(100, 1, validators[1], "0x0000000000000000", Vec::new());
+
+        // QBFT signs the extraData with an empty list of seals
+        let unsealed = bft_extra(&validators, None, &[1], Some(&[][..]));
+        let seals = bft_commit(&block, &unsealed, &[&keys[0], &keys[1], &keys[2]], Some(base_fee));
+        block.extra_data = Bytes(bft_extra(&validators, None, &[1], Some(&seals[..])));
+
+        let extra = parse_bft_extra(&block.extra_data.0, true).unwrap();
+        assert_eq!(None, extra.vote);
+        assert_eq!(1, extra.round);
+        assert_eq!(validators, parse_bft_committers(&block, Some(&ext), &extra));
+
+        // without the base fee the hash is not the committed one
+        let committers = parse_bft_committers(&block, None, &extra);
+        assert!(committers.iter().all(|c| !validators.contains(c)));
+    }
+
+    #[test]
+    fn test_aura_seal_after_london() {
+        let keys = signer_keys(1);
+        let base_fee = U256::from(1_000_000_000u64);
+        let ext = BlockExt { base_fee_per_gas: Some(base_fee), ..Default::default() };
+        let mut block = test_block(100, 0xfffffffe, keys[0].address(), "0x0000000000000000", vec![0xde, 0xad]);
+
+        let hash = bare_header_hash(&block, &block.extra_data.0, false, Some(base_fee));
+        let signature = (*sign(keys[0].secret(), &hash).unwrap())[..].to_vec();
+        block.seal_fields = vec![
+            Bytes(rlp::encode(&321_000_000u64).to_vec()),
+            Bytes(rlp::encode(&signature).to_vec()),
+        ];
+
+        let seal = parse_aura_seal(&block, Some(&ext)).unwrap();
+        assert_eq!(321_000_000, seal.step);
+        assert_eq!(signature, seal.signature);
+        assert_eq!(Some(keys[0].address()), seal.signer);
+
+        let seal = parse_aura_seal(&block, None).unwrap();
+        assert_ne!(Some(keys[0].address()), seal.signer);
+    }
+
+    #[test]
+    fn test_revert_reason_malformed_error() {
+        let parser = ContractParser::new();
+        let reason = |data: &[u8]| match parser.revert_reason(None, data).unwrap() {
+            RevertInfo::Error(reason) => Ok(reason),
+            RevertInfo::Unknown(data) => Err(data),
+            _ => panic!("unexpected revert info"),
+        };
+
+        // Error("no")
+        let mut data = vec![0x08, 0xc3, 0x79, 0xa0];
+        data.extend_from_slice(&[0u8;31]);
+        data.push(0x20);
+        data.extend_from_slice(&[0u8;31]);
+        data.push(0x02);
+        data.extend_from_slice(b"no");
+        data.extend_from_slice(&[0u8;30]);
+        assert_eq!(Ok("no".to_string()), reason(&data));
+
+        // the offset points past the data
+        data[4+31] = 0xff;
+        assert_eq!(Err(data.clone()), reason(&data));
+
+        // the selector without the string
+        assert_eq!(Err(data[..6].to_vec()), reason(&data[..6]));
+    }
+
+    #[test]
+    fn test_tx_ext_with_receipt_and_burned_fee() {
+        // eth_getTransactionByHash and eth_getTransactionReceipt of a blob tx
+        let tx_ext : TxExt = serde_json::from_value(json!({
+            "type": "0x3",
+            "maxFeePerGas": "0x77359400",
+            "maxPriorityFeePerGas": "0x3b9aca00",
+            "accessList": [],
+            "maxFeePerBlobGas": "0x10",
+            "blobVersionedHashes": [H256::from(1)],
+            "gasPrice": "0x77359400",
+        })).unwrap();
+        let receipt_ext : TxExt = serde_json::from_value(json!({
+            "type": "0x3",
+            "effectiveGasPrice": "0x59682f00",
+            "blobGasUsed": "0x20000",
+            "blobGasPrice": "0x3",
+        })).unwrap();
+
+        let ext = tx_ext.with_receipt(receipt_ext);
+        assert_eq!("blob (EIP-4844)", ext.type_name());
+        assert_eq!(Some(U256::from(2_000_000_000u64)), ext.max_fee_per_gas);
+        assert_eq!(Some(U256::from(1_000_000_000u64)), ext.max_priority_fee_per_gas);
+        assert_eq!(Some(vec![H256::from(1)]), ext.blob_versioned_hashes);
+        assert_eq!(Some(U256::from(1_500_000_000u64)), ext.effective_gas_price);
+        assert_eq!(U256::from(0x20000 * 3), ext.blob_fee());
+
+        // base fee of 0.5 gwei for 21000 gas, plus the blob fee
+        let base_fee = U256::from(500_000_000u64);
+        assert_eq!(U256::from(500_000_000u64 * 21000 + 0x20000 * 3),
+            ext.burned_fee(&base_fee, &U256::from(21000)));
+
+        // legacy transactions before london only burn the base fee
+        let legacy = TxExt::default().with_receipt(TxExt::default());
+        assert_eq!("legacy", legacy.type_name());
+        assert_eq!(U256::zero(), legacy.blob_fee());
+        assert_eq!(U256::from(21000), legacy.burned_fee(&U256::from(1), &U256::from(21000)));
+    }
+
+    #[test]
+    fn test_reward_schedule() {
+        let schedule = RewardSchedule { byzantium: 10, constantinople: 20 };
+        let wei = |s: &str| U256::from_dec_str(s).unwrap();
+
+        assert_eq!(wei("5000000000000000000"), schedule.block_reward(9));
+        assert_eq!(wei("3000000000000000000"), schedule.block_reward(10));
+        assert_eq!(wei("3000000000000000000"), schedule.block_reward(19));
+        assert_eq!(wei("2000000000000000000"), schedule.block_reward(20));
+
+        // 1/32 of the block reward for each uncle
+        assert_eq!(wei("5000000000000000000"), schedule.miner_reward(9, 0));
+        assert_eq!(wei("5312500000000000000"), schedule.miner_reward(9, 2));
+        assert_eq!(wei("3093750000000000000"), schedule.miner_reward(10, 1));
+        assert_eq!(wei("2062500000000000000"), schedule.miner_reward(20, 1));
+
+        // uncles get the reward of the including block, 7/8 to 1/8 of it
+        assert_eq!(wei("2625000000000000000"), schedule.uncle_reward(10, 9));
+        assert_eq!(wei("1750000000000000000"), schedule.uncle_reward(20, 19));
+        assert_eq!(wei("375000000000000000"), schedule.uncle_reward(17, 10));
+        assert_eq!(U256::zero(), schedule.uncle_reward(18, 10));
+        assert_eq!(U256::zero(), schedule.uncle_reward(10, 10));
+    }
+
+    #[test]
+    fn test_source_map_file() {
+        // PUSH1 1, PUSH1 2, ADD, STOP
+        let code = [0x60, 0x01, 0x60, 0x02, 0x01, 0x00];
+        let source = "line one\nline two\nline three\n";
+        let srcmap = "0:8:0;9:8:1;18:10:0;9:8:-1";
+
+        let map = SourceMap::parse(srcmap, &code, 0);
+        assert_eq!(Some((1, "line one".to_string())), map.source_line(source, 0));
+        assert_eq!(None, map.source_line(source, 1));
+        assert_eq!(None, map.source_line(source, 2));
+        assert_eq!(Some((3, "line three".to_string())), map.source_line(source, 4));
+        assert_eq!(None, map.source_line(source, 5));
+
+        // another source of the same compilation
+        let map = SourceMap::parse(srcmap, &code, 1);
+        assert_eq!(None, map.source_line(source, 0));
+        assert_eq!(Some((2, "line two".to_string())), map.source_line(source, 2));
+    }
+
+    #[test]
+    fn test_read_genesis_forks() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/extra/geth-devnet/config/genesis.json");
+        assert_eq!(GenesisForks {
+            byzantium_block: Some(4),
+            constantinople_block: Some(5),
+            petersburg_block: None,
+        }, read_genesis_forks(path).unwrap());
+    }
+
+    #[test]
+    fn test_read_genesis_alloc() {
+        // the devnet alloc has unprefixed addresses and hex balances
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/extra/geth-devnet/config/genesis.json");
+        let allocs = read_genesis_alloc(path).unwrap();
+        assert_eq!(257, allocs.len());
+        assert_eq!(Address::from(0), allocs[0].0);
+        assert_eq!(U256::from(1), allocs[0].1.balance);
+        assert_eq!(Address::from(0xff), allocs[255].0);
+        let (addr, alloc) = &allocs[256];
+        assert_eq!(hex_to_addr("0x83a909262608c650bd9b0ae06e29d90d0f67ac5e").unwrap(), *addr);
+        assert_eq!(U256::from(2).pow(U256::from(249)), alloc.balance);
+        assert!(alloc.code.is_empty() && alloc.storage.is_empty());
+
+        // decimal quantities and not zero-padded storage words
+        let path = std::env::temp_dir().join("crate_test_genesis.json");
+        std::fs::write(&path, r#"{
+            "alloc": {
+                "0x0000000000000000000000000000000000000001": {
+                    "balance": "1000000000000000000",
+                    "nonce": "7",
+                    "code": "6001",
+                    "storage": { "0x0": "0x1", "0x01": "0x2a" }
+                }
+            }
+        }"#).unwrap();
+        let allocs = read_genesis_alloc(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(1, allocs.len());
+        let (addr, alloc) = &allocs[0];
+        assert_eq!(Address::from(1), *addr);
+        assert_eq!(U256::from_dec_str("1000000000000000000").unwrap(), alloc.balance);
+        assert_eq!(U256::from(7), alloc.nonce);
+        assert_eq!(vec![0x60, 0x01], alloc.code);
+        assert_eq!(vec![
+            (H256::from(0), H256::from(1)),
+            (H256::from(1), H256::from(42)),
+        ], alloc.storage);
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+use web3::api::EthSubscribe;
+use web3::futures::{Future, Stream};
+use web3::transports::{EventLoopHandle, Http, Ipc, WebSocket};
+use web3::{rpc, BatchTransport, DuplexTransport, ErrorKind, RequestId, Transport};
+
+use super::failover::{INITIAL_BACKOFF_MS, MAX_BACKOFF_MS};
+
+/// A transport selected by the web3_url scheme: http(s)://, ws(s)://
+///   or the path of an ipc socket
+#[derive(Debug, Clone)]
+pub enum AnyTransport {
+    Http(Http),
+    WebSocket(WebSocket),
+    Ipc(Ipc),
+}
+
+/// check if the url points to a transport that supports subscriptions
+fn is_websocket(url: &str) -> bool {
+    url.starts_with("ws://") || url.starts_with("wss://")
+}
+
+/// check if the url points to an http endpoint
+fn is_http(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// check if the url points to an ipc socket, ipc:// or a path without scheme
+fn is_ipc(url: &str) -> bool {
+    url.starts_with("ipc://") || !url.contains("://")
+}
+
+/// get the ipc socket path from an url
+fn ipc_path(url: &str) -> &str {
+    url.trim_start_matches("ipc://")
+}
+
+impl AnyTransport {
+
+    /// create a new transport for the url
+    pub fn new(url: &str) -> web3::Result<(EventLoopHandle, Self)> {
+        if is_websocket(url) {
+            WebSocket::new(url).map(|(eloop, t)| (eloop, AnyTransport::WebSocket(t)))
+        } else if is_http(url) {
+            Http::new(url).map(|(eloop, t)| (eloop, AnyTransport::Http(t)))
+        } else if is_ipc(url) {
+            Ipc::new(ipc_path(url)).map(|(eloop, t)| (eloop, AnyTransport::Ipc(t)))
+        } else {
+            Err(ErrorKind::Transport(format!("unsupported web3 url {}", url)).into())
+        }
+    }
+}
+
+impl Transport for AnyTransport {
+    type Out = Box<dyn Future<Item = rpc::Value, Error = web3::Error>>;
+
+    fn prepare(&self, method: &str, params: Vec<rpc::Value>) -> (RequestId, rpc::Call) {
+        match self {
+            AnyTransport::Http(t) => t.prepare(method, params),
+            AnyTransport::WebSocket(t) => t.prepare(method, params),
+            AnyTransport::Ipc(t) => t.prepare(method, params),
+        }
+    }
+
+    fn send(&self, id: RequestId, request: rpc::Call) -> Self::Out {
+        match self {
+            AnyTransport::Http(t) => Box::new(t.send(id, request)),
+            AnyTransport::WebSocket(t) => Box::new(t.send(id, request)),
+            AnyTransport::Ipc(t) => Box::new(t.send(id, request)),
+        }
+    }
+}
+
+impl BatchTransport for AnyTransport {
+    type Batch = Box<dyn Future<Item = Vec<Result<rpc::Value, web3::Error>>, Error = web3::Error>>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, rpc::Call)>,
+    {
+        match self {
+            AnyTransport::Http(t) => Box::new(t.send_batch(requests)),
+            AnyTransport::WebSocket(t) => Box::new(t.send_batch(requests)),
+            AnyTransport::Ipc(t) => Box::new(t.send_batch(requests)),
+        }
+    }
+}
+
+/// forward the block numbers of the new heads to the channel until the
+///   subscription finishes, returns false if the receiver was dropped
+fn forward_new_heads<T: DuplexTransport>(transport: T, heads: &Sender<u64>) -> web3::Result<bool> {
+    let subscription = EthSubscribe::new(transport).subscribe_new_heads().wait()?;
+    for header in subscription.wait() {
+        if let Some(number) = header?.number {
+            if heads.send(number.low_u64()).is_err() {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// subscribe to newHeads, returning a channel that receives the number of each new
+///   block. When the subscription fails it is made again in the next url that
+///   supports subscriptions, after a backoff. Returns None if none of the urls
+///   supports subscriptions (http)
+pub fn subscribe_new_heads(urls: &[String]) -> Option<Receiver<u64>> {
+    let urls : Vec<String> = urls.iter().filter(|url| !is_http(url)).cloned().collect();
+    if urls.is_empty() {
+        return None;
+    }
+
+    let (heads, heads_rx) = channel();
+    thread::spawn(move || {
+        let mut backoff = INITIAL_BACKOFF_MS;
+        for url in urls.iter().cycle() {
+            let result = if is_websocket(url) {
+                WebSocket::new(url).and_then(|(_eloop, t)| forward_new_heads(t, &heads))
+            } else {
+                Ipc::new(ipc_path(url)).and_then(|(_eloop, t)| forward_new_heads(t, &heads))
+            };
+            match result {
+                Ok(false) => break,
+                Ok(true) => {
+                    warn!("Subscription to newHeads in {} finished", url);
+                    backoff = INITIAL_BACKOFF_MS;
+                }
+                Err(err) => {
+                    error!("Subscription to newHeads in {} failed: {:?}", url, err);
+                    backoff = (backoff * 2).min(MAX_BACKOFF_MS);
+                }
+            }
+            thread::sleep(Duration::from_millis(backoff));
+        }
+    });
+
+    Some(heads_rx)
+}
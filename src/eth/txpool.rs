@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, HashMap};
+
+use web3::api::Namespace;
+use web3::helpers::CallFuture;
+use web3::types::{Address, Transaction};
+use web3::Transport;
+
+/// `TxPool` namespace, for the txpool_ calls
+#[derive(Debug, Clone)]
+pub struct TxPool<T> {
+    transport: T,
+}
+
+/// A transport for txpool_ calls
+impl<T: Transport> Namespace<T> for TxPool<T> {
+    fn new(transport: T) -> Self
+    where
+        Self: Sized,
+    {
+        TxPool { transport }
+    }
+
+    fn transport(&self) -> &T {
+        &self.transport
+    }
+}
+
+impl<T: Transport> TxPool<T> {
+
+    /// Retrieve the transactions in the pool by calling txpool_content
+    pub fn content(&self) -> CallFuture<TxPoolContent, T::Out> {
+        CallFuture::new(
+            self.transport.execute("txpool_content", vec![])
+        )
+    }
+
+    /// Retrieve the transactions in the pool sent by the address by calling
+    ///   txpool_contentFrom
+    pub fn content_from(&self, addr: Address) -> CallFuture<TxPoolContentFrom, T::Out> {
+        CallFuture::new(
+            self.transport.execute("txpool_contentFrom", vec![web3::helpers::serialize(&addr)])
+        )
+    }
+}
+
+/// The transactions in the pool by sender and nonce, pending are the ones
+///   that can be mined and queued the ones that cannot be executed yet
+#[derive(Debug, Default, Deserialize)]
+pub struct TxPoolContent {
+    #[serde(default)]
+    pub pending : BTreeMap<Address,BTreeMap<String,Transaction>>,
+    #[serde(default)]
+    pub queued : BTreeMap<Address,BTreeMap<String,Transaction>>,
+}
+
+/// The transactions in the pool of a sender by nonce
+#[derive(Debug, Default, Deserialize)]
+pub struct TxPoolContentFrom {
+    #[serde(default)]
+    pub pending : BTreeMap<String,Transaction>,
+    #[serde(default)]
+    pub queued : BTreeMap<String,Transaction>,
+}
+
+impl TxPoolContentFrom {
+
+    /// get the pool content with only the transactions of the sender
+    pub fn into_content(self, sender: Address) -> TxPoolContent {
+        let mut content = TxPoolContent::default();
+        if !self.pending.is_empty() {
+            content.pending.insert(sender, self.pending);
+        }
+        if !self.queued.is_empty() {
+            content.queued.insert(sender, self.queued);
+        }
+        content
+    }
+}
+
+/// Why a transaction in the pool is not mined yet
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolStatus {
+    /// can be mined, waiting to be included
+    Pending,
+    /// waiting for the transactions with the nonces in the range, also the
+    ///   queued transactions that follow it
+    NonceGap(u64,u64),
+    /// not executable for other reasons, like not enough balance
+    Queued,
+}
+
+/// A transaction in the pool
+#[derive(Debug, Clone)]
+pub struct PoolTx {
+    pub tx : Transaction,
+    pub status : PoolStatus,
+}
+
+/// sort the transactions of a sender by nonce
+fn by_nonce(txs: Option<BTreeMap<String,Transaction>>) -> Vec<Transaction> {
+    let mut txs : Vec<_> = txs.unwrap_or_default().into_iter().map(|(_,tx)| tx).collect();
+    txs.sort_by_key(|tx| tx.nonce);
+    txs
+}
+
+impl TxPoolContent {
+
+    /// number of transactions in the pool
+    pub fn count(&self) -> usize {
+        self.pending.values().chain(self.queued.values()).map(|txs| txs.len()).sum()
+    }
+
+    /// number of queued transactions in the pool
+    pub fn queued_count(&self) -> usize {
+        self.queued.values().map(|txs| txs.len()).sum()
+    }
+
+    /// the senders that have queued transactions without pending ones in the
+    ///   page of into_txs that skips and takes the transactions, their account
+    ///   nonce is needed to find the first nonce gap
+    pub fn queued_senders(&self, skip: usize, take: usize) -> Vec<Address> {
+        let mut senders : Vec<&Address> = self.pending.keys().chain(self.queued.keys()).collect();
+        senders.sort();
+        senders.dedup();
+
+        let mut first = 0;
+        let mut queued_senders = Vec::new();
+        for sender in senders {
+            let count = self.pending.get(sender).map_or(0, |txs| txs.len())
+                + self.queued.get(sender).map_or(0, |txs| txs.len());
+            if first < skip.saturating_add(take) && first + count > skip
+                && !self.pending.contains_key(sender) {
+                queued_senders.push(*sender);
+            }
+            first += count;
+        }
+        queued_senders
+    }
+
+    /// get all the transactions sorted by sender and nonce, with the nonce
+    ///   gaps that prevent the queued ones from being mined. nonces has the
+    ///   account nonce of the queued_senders
+    pub fn into_txs(self, nonces: &HashMap<Address,u64>) -> Vec<PoolTx> {
+        let TxPoolContent { mut pending, mut queued } = self;
+
+        let mut senders : Vec<Address> = pending.keys().chain(queued.keys()).cloned().collect();
+        senders.sort();
+        senders.dedup();
+
+        let mut txs = Vec::new();
+        for sender in senders {
+            let pending = by_nonce(pending.remove(&sender));
+            let queued = by_nonce(queued.remove(&sender));
+
+            // the next nonce is the one after the pending transactions
+            let mut next_nonce = pending.last()
+                .map(|tx| tx.nonce.low_u64() + 1)
+                .or_else(|| nonces.get(&sender).cloned());
+
+            for tx in pending {
+                txs.push(PoolTx { tx, status : PoolStatus::Pending });
+            }
+
+            // the transactions after a gap are blocked by it until it is filled
+            let mut gap = None;
+            for tx in queued {
+                let nonce = tx.nonce.low_u64();
+                if let Some(next) = next_nonce.filter(|next| *next < nonce) {
+                    gap = Some(PoolStatus::NonceGap(next, nonce - 1));
+                }
+                let status = gap.clone().unwrap_or(PoolStatus::Queued);
+                next_nonce = Some(nonce + 1);
+                txs.push(PoolTx { tx, status });
+            }
+        }
+        txs
+    }
+}
@@ -1,13 +1,175 @@
 use rustc_hex::{FromHex, FromHexError};
 use web3::types::{Address, Block, H256,U256};
+use rlp::RlpStream;
+use keccak_hash::keccak;
+use ethkey::{Signature,recover};
 
-#[derive(Debug,PartialEq,Serialize,Deserialize)]
+/// A call made during the execution of a transaction. Calls are numbered
+///   from 1 in depth-first order, and parent is the number of the calling
+///   one, or 0 if it was called by the transaction
+#[derive(Debug,Default,PartialEq,Serialize,Deserialize)]
 pub struct InternalTx {
     pub from     : Address,
     pub to       : Option<Address>,
     pub contract : Option<Address>,
     pub value    : U256,
     pub input    : Vec<u8>,
+    #[serde(default)]
+    pub depth    : u32,
+    #[serde(default)]
+    pub parent   : u64,
+    #[serde(default)]
+    pub call_type : String,
+    #[serde(default)]
+    pub gas      : U256,
+    #[serde(default)]
+    pub gas_used : U256,
+    #[serde(default)]
+    pub output   : Vec<u8>,
+    #[serde(default)]
+    pub error    : Option<String>,
+}
+
+/// A beacon chain withdrawal, the amount is in gwei
+#[derive(Debug,Clone,Default,PartialEq,Serialize,Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Withdrawal {
+    pub index : U256,
+    pub validator_index : U256,
+    pub address : Address,
+    pub amount : U256,
+}
+
+/// The block header fields added after london, not available in web3::types::Block
+#[derive(Debug,Clone,Default,PartialEq,Serialize,Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockExt {
+    #[serde(default)]
+    pub base_fee_per_gas : Option<U256>,
+    #[serde(default)]
+    pub withdrawals_root : Option<H256>,
+    #[serde(default)]
+    pub blob_gas_used : Option<U256>,
+    #[serde(default)]
+    pub excess_blob_gas : Option<U256>,
+    #[serde(default)]
+    pub parent_beacon_block_root : Option<H256>,
+    #[serde(default)]
+    pub withdrawals : Vec<Withdrawal>,
+}
+
+impl BlockExt {
+
+    /// number of header fields added after london that the block has
+    pub fn header_len(&self) -> usize {
+        [
+            self.base_fee_per_gas.is_some(),
+            self.withdrawals_root.is_some(),
+            self.blob_gas_used.is_some(),
+            self.excess_blob_gas.is_some(),
+            self.parent_beacon_block_root.is_some(),
+        ].iter().filter(|present| **present).count()
+    }
+
+    /// append the header fields added after london, in header order
+    pub fn append_header(&self, stream: &mut RlpStream) {
+        if let Some(base_fee_per_gas) = &self.base_fee_per_gas {
+            stream.append(base_fee_per_gas);
+        }
+        if let Some(withdrawals_root) = &self.withdrawals_root {
+            stream.append(withdrawals_root);
+        }
+        if let Some(blob_gas_used) = &self.blob_gas_used {
+            stream.append(blob_gas_used);
+        }
+        if let Some(excess_blob_gas) = &self.excess_blob_gas {
+            stream.append(excess_blob_gas);
+        }
+        if let Some(parent_beacon_block_root) = &self.parent_beacon_block_root {
+            stream.append(parent_beacon_block_root);
+        }
+    }
+}
+
+/// An entry of the access list of an EIP-2930 transaction
+#[derive(Debug,Clone,Default,PartialEq,Serialize,Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListItem {
+    pub address : Address,
+    pub storage_keys : Vec<H256>,
+}
+
+/// The typed transaction fields, not available in web3::types::Transaction
+///   and TransactionReceipt. The transaction fields and the receipt fields
+///   are read separately and merged with with_receipt
+#[derive(Debug,Clone,Default,PartialEq,Serialize,Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxExt {
+    #[serde(default, rename = "type")]
+    pub tx_type : Option<U256>,
+    #[serde(default)]
+    pub max_fee_per_gas : Option<U256>,
+    #[serde(default)]
+    pub max_priority_fee_per_gas : Option<U256>,
+    #[serde(default)]
+    pub access_list : Option<Vec<AccessListItem>>,
+    #[serde(default)]
+    pub max_fee_per_blob_gas : Option<U256>,
+    #[serde(default)]
+    pub blob_versioned_hashes : Option<Vec<H256>>,
+    #[serde(default)]
+    pub effective_gas_price : Option<U256>,
+    #[serde(default)]
+    pub blob_gas_used : Option<U256>,
+    #[serde(default)]
+    pub blob_gas_price : Option<U256>,
+}
+
+impl TxExt {
+
+    /// take the fields that are only in the receipt
+    pub fn with_receipt(self, receipt: TxExt) -> TxExt {
+        TxExt {
+            effective_gas_price : receipt.effective_gas_price,
+            blob_gas_used : receipt.blob_gas_used,
+            blob_gas_price : receipt.blob_gas_price,
+            ..self
+        }
+    }
+
+    /// name of the transaction envelope
+    pub fn type_name(&self) -> &'static str {
+        match self.tx_type.map(|t| t.low_u64()) {
+            None | Some(0) => "legacy",
+            Some(1) => "access list (EIP-2930)",
+            Some(2) => "dynamic fee (EIP-1559)",
+            Some(3) => "blob (EIP-4844)",
+            Some(4) => "set code (EIP-7702)",
+            Some(_) => "unknown",
+        }
+    }
+
+    /// the fee paid for the blobs, that is burned
+    pub fn blob_fee(&self) -> U256 {
+        match (self.blob_gas_used, self.blob_gas_price) {
+            (Some(used), Some(price)) => used * price,
+            _ => U256::zero(),
+        }
+    }
+
+    /// the fee burned by the transaction, base fee and blob fee
+    pub fn burned_fee(&self, base_fee: &U256, gas_used: &U256) -> U256 {
+        *base_fee * *gas_used + self.blob_fee()
+    }
+}
+
+/// An account funded or predeployed in the genesis block
+#[derive(Debug,Clone,Default,PartialEq,Serialize,Deserialize)]
+pub struct GenesisAlloc {
+    pub balance : U256,
+    pub nonce : U256,
+    pub code : Vec<u8>,
+    pub storage : Vec<(H256,H256)>,
 }
 
 pub fn hex_to_vec(s: &str) -> Result<Vec<u8>, FromHexError> {
@@ -29,7 +191,51 @@ pub fn hex_to_h256(s: &str) -> Result<H256, FromHexError> {
 }
 
 pub fn hex_to_u256(s: &str) -> Result<U256, FromHexError> {
-    hex_to_vec(s).map(|v| U256::from_big_endian(&v)) // TODO: check
+    // quantities are not zero-padded, e.g. 0x0
+    let digits = s.trim_start_matches("0x");
+    let v : Vec<u8> = if digits.len() % 2 == 0 {
+        digits.from_hex()?
+    } else {
+        format!("0{}",digits).from_hex()?
+    };
+    Ok(U256::from_big_endian(&v))
+}
+
+/// get the address stored in the lower bytes of a word
+#[allow(deprecated)]
+pub fn u256_to_addr(v: &U256) -> Address {
+    let mut word = [0u8; 32];
+    v.to_big_endian(&mut word);
+    Address::from_slice(&word[12..])
+}
+
+/// compute the address of a contract created by a transaction
+#[allow(deprecated)]
+pub fn contract_address(from: &Address, nonce: &U256) -> Address {
+    let mut stream = RlpStream::new_list(2);
+    stream.append(from).append(nonce);
+    let hash = keccak(&stream.out());
+    Address::from_slice(&hash.0[12..])
+}
+
+/// recover the signer of a hash from a 65 bytes r,s,v signature
+#[allow(deprecated)]
+pub fn recover_signer(signature: &[u8], hash: &H256) -> Option<Address> {
+    if signature.len() != 65 {
+        return None;
+    }
+    let r = H256::from_slice(&signature[0..32]);
+    let s = H256::from_slice(&signature[32..64]);
+    let v = signature[64];
+
+    let sig = Signature::from_rsv(&r, &s, v);
+    if sig.is_valid() {
+        if let Ok(pbk) = recover(&sig, hash) {
+            let pbk_hash = keccak(pbk);
+            return Some(Address::from_slice(&pbk_hash.0[12..]));
+        }
+    }
+    None
 }
 
 pub fn into_block<T1, T2, F>(block: Block<T1>, f: F) -> Block<T2>
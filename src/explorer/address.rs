@@ -10,6 +10,20 @@ use super::super::eth::{
 
 use super::super::state::GlobalState;
 use super::utils;
+use super::super::db::AddrEventKind;
+
+/// events shown in the other activity table
+const MAX_EVENTS : usize = 50;
+
+/// render the kind of an address event
+fn event_kind(kind: AddrEventKind) -> &'static str {
+    match kind {
+        AddrEventKind::Withdrawal => "withdrawal",
+        AddrEventKind::BlockReward => "block reward",
+        AddrEventKind::UncleReward => "uncle reward",
+        AddrEventKind::Genesis => "genesis",
+    }
+}
 
 /// render the address info
 pub fn render(
@@ -26,8 +40,37 @@ pub fn render(
 
     // get current blockchain data
 
-    let balance = reader.current_balance(addr)?;
+    let balance = match reader.current_balance(addr)? {
+        Some(balance) => hr.ether(&balance,false),
+        None => "unknown".to_string(),
+    };
     let code = reader.current_code(addr)?;
+    let contract = db.get_contract(addr)?;
+    let genesis = db.get_genesis_alloc(addr)?;
+
+    // if the node is not available, assume that verified contracts and
+    //   predeployed contracts have code
+    let genesis_code = genesis.as_ref()
+        .map(|alloc| alloc.code.clone())
+        .filter(|code| !code.is_empty());
+    let hascode = match &code {
+        Some(code) => !code.0.is_empty(),
+        None => contract.is_some() || genesis_code.is_some(),
+    };
+
+    // get the genesis allocation
+
+    let genesis = genesis.map(|alloc| json!({
+        "balance"   : hr.ether(&alloc.balance,false),
+        "nonce"     : alloc.nonce.low_u64(),
+        "code_size" : alloc.code.len(),
+        "storage"   : alloc.storage.iter()
+            .map(|(key,value)| json!({
+                "key"   : format!("0x{:x}",key),
+                "value" : format!("0x{:x}",value),
+            }))
+            .collect::<Vec<_>>(),
+    }));
 
     // get linked transactions (internal and external)
 
@@ -53,34 +96,67 @@ pub fn render(
         }
     }
 
+    // get other activity, like withdrawals
+
+    let events_count = db.count_addr_events(&addr)?;
+    let events : Vec<_> = db.iter_addr_events(&addr)
+        .take(MAX_EVENTS)
+        .map(|event| json!({
+            "blockno" : hr.blockno(event.blockno),
+            "kind"    : event_kind(event.kind),
+            "value"   : hr.ether(&event.value,false),
+        }))
+        .collect();
+
+    // get the transactions sent in the pool, waiting to be mined
+
+    let mut pool = Vec::new();
+    let pool_content = reader.pool_content(Some(addr))?.unwrap_or_default();
+    let pool_txs = reader.pool_txs(pool_content, 0, std::usize::MAX)?;
+    if !pool_txs.is_empty() {
+        let base_fee = reader.block_ext(reader.current_block_number()?)?
+            .and_then(|block| block.base_fee_per_gas);
+        for ptx in &pool_txs {
+            pool.push(hr.pool_tx(ptx, &base_fee)?);
+        }
+    }
+
     // render
 
-    if !code.0.is_empty() {
+    if hascode {
 
         let mut solcversions = installed_compilers(&cfg)?;
         if cfg.solc_bypass {
             solcversions.push(ONLY_ABI.to_string());
         }
 
-        let rawcode = hr.bytes(&code.0,50);
-        let contract = db.get_contract(addr)?;
+        let rawcode = code.as_ref()
+            .map(|code| code.0.clone())
+            .or(genesis_code)
+            .map(|code| hr.bytes(&code,50))
+            .unwrap_or_default();
 
         if let Some(contract) = contract {
             
-            let can_set_source = contract.compiler == ONLY_ABI;
+            let can_set_source = contract.compiler == ONLY_ABI && !reader.is_stale();
 
             Ok(hb.render(
                 "address.handlebars",
                 &json!({
                     "ui_title" : ge.cfg.ui_title,
                     "address" : format!("0x{:x}",addr),
-                    "balance" : hr.ether(&balance,false),
+                    "stale" : reader.is_stale(),
+                    "balance" : balance,
                     "txs" : txs,
                     "txs_count" : count_addr_tx_links,
                     "has_next_page": pg.next_page.is_some(),
                     "next_page": pg.next_page.unwrap_or(0),
                     "has_prev_page": pg.prev_page.is_some(),
                     "prev_page": pg.prev_page.unwrap_or(0),                    
+                    "events" : events,
+                    "events_count" : events_count,
+                    "genesis" : genesis,
+                    "pool" : pool,
                     "hascode" : true,
                     "rawcode" : rawcode,
                     "can_set_source" : can_set_source,
@@ -100,16 +176,21 @@ pub fn render(
                 &json!({
                     "ui_title" : ge.cfg.ui_title,
                     "address" : format!("0x{:x}",addr),
-                    "balance" : hr.ether(&balance,false),
+                    "stale" : reader.is_stale(),
+                    "balance" : balance,
                     "txs" : txs,
                     "txs_count" : count_addr_tx_links,
                     "has_next_page": pg.next_page.is_some(),
                     "next_page": pg.next_page.unwrap_or(0),
                     "has_prev_page": pg.prev_page.is_some(),
                     "prev_page": pg.prev_page.unwrap_or(0),                    
+                    "events" : events,
+                    "events_count" : events_count,
+                    "genesis" : genesis,
+                    "pool" : pool,
                     "hascode" : true,
                     "rawcode" : rawcode,
-                    "can_set_source" : true,
+                    "can_set_source" : !reader.is_stale(),
                     "solcversions" : solcversions,
                 })
             )?)
@@ -123,13 +204,18 @@ pub fn render(
             &json!({
                 "ui_title" : ge.cfg.ui_title,
                 "address" : format!("0x{:x}",addr),
-                "balance" : hr.ether(&balance,false),
+                "stale" : reader.is_stale(),
+                "balance" : balance,
                 "txs"     : txs,
                 "txs_count" : count_addr_tx_links,
                 "has_next_page": pg.next_page.is_some(),
                 "next_page": pg.next_page.unwrap_or(0),
                 "has_prev_page": pg.prev_page.is_some(),
                 "prev_page": pg.prev_page.unwrap_or(0),                    
+                "events" : events,
+                "events_count" : events_count,
+                "genesis" : genesis,
+                "pool" : pool,
                 "hascode" : false,
             })
         )?)
@@ -2,9 +2,46 @@ use super::error::*;
 use super::html::*;
 use super::utils;
 
+use web3::types::{Block,U256};
+use rustc_hex::ToHex;
+
+use super::super::bootstrap::{AURA,IBFT,QBFT};
 use super::super::eth::BlockchainReader;
+use super::super::eth::types::BlockExt;
+use super::super::eth::besu::bft::{parse_bft_extra,parse_bft_committers};
+use super::super::eth::parity::aura::parse_aura_seal;
 use super::super::state::GlobalState;
 
+/// render the consensus fields of aura, IBFT 2.0 and QBFT blocks
+fn consensus<T>(ge: &GlobalState, hr: &HtmlRender, block: &Block<T>, ext: Option<&BlockExt>) -> serde_json::Value {
+    let client = ge.cfg.web3_client.as_str();
+    if client == AURA {
+        if let Some(seal) = parse_aura_seal(block, ext) {
+            return json!({
+                "step"      : seal.step,
+                "signature" : seal.signature.to_hex::<String>(),
+                "signer"    : seal.signer.map(|signer| hr.addr(&signer)),
+                "bad_signer": seal.signer != Some(block.author),
+            });
+        }
+    } else if client == IBFT || client == QBFT {
+        if let Some(extra) = parse_bft_extra(&block.extra_data.0, client == QBFT) {
+            let committers = parse_bft_committers(block, ext, &extra);
+            return json!({
+                "round"      : extra.round,
+                "validators" : extra.validators.iter().map(|v| hr.addr(v)).collect::<Vec<_>>(),
+                "vote"       : extra.vote.map(|vote| json!({
+                    "recipient" : hr.addr(&vote.recipient),
+                    "authorize" : vote.authorize,
+                })),
+                "committers" : committers.iter().map(|c| hr.addr(c)).collect::<Vec<_>>(),
+                "seals"      : extra.seals.len(),
+            });
+        }
+    }
+    serde_json::Value::Null
+}
+
 /// render the block page
 pub fn render(
     ge: &GlobalState,
@@ -18,12 +55,54 @@ pub fn render(
     if let Some(block) = reader.block_with_txs(blockno)? {
 
         let author = utils::block_author(&ge.cfg,&block);
+        let unconfirmed = reader.is_unconfirmed(blockno,reader.current_block_number()?);
         let rawextra = hr.bytes(&block.extra_data.0,32);
+        let seal_violation = ge.db.get_seal_violation(blockno)?.map(|v| v.to_string());
+
+        // fields added after london, withdrawals amounts are in gwei
+        let ext = reader.block_ext(blockno)?;
+        let consensus = consensus(&ge, &hr, &block, ext.as_ref());
+        let withdrawals : Vec<_> = ext.iter()
+            .flat_map(|ext| ext.withdrawals.iter())
+            .map(|w| json!({
+                "index"           : w.index.low_u64(),
+                "validator_index" : w.validator_index.low_u64(),
+                "address"         : hr.addr(&w.address),
+                "amount"          : hr.ether(&(w.amount * U256::from(1_000_000_000u64)),false),
+            }))
+            .collect();
 
-        // get transactions
+        // uncles and the static rewards of the miners
+        let schedule = ge.reward_schedule;
+        let uncles : Vec<_> = reader.uncles(&block)?.iter()
+            .map(|uncle| {
+                let uncle_blockno = uncle.number.unwrap().low_u64();
+                json!({
+                    "hash"   : uncle.hash,
+                    "number" : hr.blockno(uncle_blockno),
+                    "miner"  : hr.addr(&uncle.author),
+                    "reward" : schedule.map(|schedule| hr.ether(&schedule.uncle_reward(blockno, uncle_blockno),false)),
+                })
+            })
+            .collect();
+        let reward = schedule.map(|schedule| hr.ether(&schedule.miner_reward(blockno, block.uncles.len()),false));
 
+        // get transactions, the blob fees are burned too. The burned amount is
+        //   not shown if the blob fees of some transaction cannot be read
+
+        let base_fee = ext.as_ref().and_then(|ext| ext.base_fee_per_gas);
+        let blob_gas_used = ext.as_ref().and_then(|ext| ext.blob_gas_used).unwrap_or_default();
+        let (mut blob_gas, mut blob_fees) = (U256::zero(), U256::zero());
         let mut txs = Vec::new();
         for tx in &block.transactions {
+            if blob_gas < blob_gas_used {
+                if let Some(tx_ext) = reader.tx_ext(&tx.hash)? {
+                    if let (Some(used), Some(_)) = (tx_ext.blob_gas_used, tx_ext.blob_gas_price) {
+                        blob_gas = blob_gas + used;
+                        blob_fees = blob_fees + tx_ext.blob_fee();
+                    }
+                }
+            }
             if tx.to.is_some() {
                 txs.push(hr.tx(&tx,&None)?);
             } else {
@@ -31,6 +110,9 @@ pub fn render(
                 txs.push(hr.tx(&tx,&rcpt)?);
             }
         }
+        let burned = base_fee
+            .filter(|_| blob_gas == blob_gas_used)
+            .map(|base_fee| base_fee * block.gas_used + blob_fees);
 
         // render
 
@@ -38,10 +120,21 @@ pub fn render(
             "block.handlebars",
             &json!({
                 "ui_title"         : ge.cfg.ui_title,
+                "stale"            : reader.is_stale(),
                 "blockno"          : hr.blockno(blockno).text,
+                "unconfirmed"      : unconfirmed,
                 "parent_hash"      : block.parent_hash,
                 "uncles_hash"      : block.uncles_hash,
                 "author"           : hr.addr(&author),
+                "seal_violation"   : seal_violation,
+                "consensus"        : consensus,
+                "base_fee"         : base_fee.map(|fee| hr.gwei(&fee,false)),
+                "burned"           : burned.map(|burned| hr.ether(&burned,false)),
+                "withdrawals_root" : ext.as_ref().and_then(|ext| ext.withdrawals_root),
+                "blob_gas_used"    : ext.as_ref().and_then(|ext| ext.blob_gas_used).map(|gas| gas.low_u64()),
+                "excess_blob_gas"  : ext.as_ref().and_then(|ext| ext.excess_blob_gas).map(|gas| gas.low_u64()),
+                "parent_beacon_root" : ext.as_ref().and_then(|ext| ext.parent_beacon_block_root),
+                "withdrawals"      : withdrawals,
                 "state_root"       : block.state_root,
                 "receipts_root"    : block.receipts_root,
                 "gas_used"         : block.gas_used.low_u64(),
@@ -51,7 +144,8 @@ pub fn render(
                 "difficulty"       : block.difficulty,
                 "total_difficulty" : block.total_difficulty,
                 "seal_fields"      : block.seal_fields,
-                "uncles"           : block.uncles,
+                "uncles"           : uncles,
+                "reward"           : reward,
                 "txs"              : txs
             }),
         )?)
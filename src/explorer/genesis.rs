@@ -0,0 +1,45 @@
+use super::error::Result;
+use super::html::HtmlRender;
+use super::utils;
+
+use super::super::state::GlobalState;
+
+/// render the genesis allocations page
+pub fn render(
+    ge: &GlobalState,
+    page_no : u64,
+) -> Result<String> {
+
+    let hr = HtmlRender::new(&ge);
+    let db = &ge.db;
+    let hb = &ge.hb;
+
+    let count_genesis_allocs = db.count_genesis_allocs()?;
+    let pg = utils::paginate(count_genesis_allocs,15,page_no);
+
+    let mut allocs = Vec::new();
+    if pg.from <= pg.to {
+        let it = db.iter_genesis_allocs().skip(pg.from as usize);
+        for (addr, alloc) in it.take((pg.to-pg.from) as usize) {
+            allocs.push(json!({
+                "address"   : hr.addr(&addr),
+                "balance"   : hr.ether(&alloc.balance,false),
+                "code_size" : alloc.code.len(),
+                "storage"   : alloc.storage.len(),
+            }));
+        }
+    }
+
+    Ok(hb.render(
+        "genesis.handlebars",
+        &json!({
+            "ui_title" : ge.cfg.ui_title,
+            "allocs" : allocs,
+            "allocs_count" : count_genesis_allocs,
+            "has_next_page": pg.next_page.is_some(),
+            "next_page": pg.next_page.unwrap_or(0),
+            "has_prev_page": pg.prev_page.is_some(),
+            "prev_page": pg.prev_page.unwrap_or(0),
+        }),
+    )?)
+}
@@ -30,13 +30,14 @@ pub fn render(
             let gas_limit =  block.gas_limit.low_u64() / 100_000;
             blocks.push(json!({
                 "block"     : hr.blockno(block_no),
+                "unconfirmed" : reader.is_unconfirmed(block_no,last_blockno),
                 "tx_count"  : block.transactions.len(),
                 "author"    : hr.addr(&author),
                 "timestamp" : hr.timestamp(&block.timestamp),
                 "gas_used"   : format!("{}%",gas_used_p), 
                 "gas_limit"  : format!("{}.{}M",gas_limit/10,gas_limit%10)
             }));
-        } else {
+        } else if !reader.is_stale() {
             return Err(Error::Unexpected);
         }
     }
@@ -47,6 +48,8 @@ pub fn render(
         "home.handlebars",
         &json!({
             "ui_title" : ge.cfg.ui_title,
+            "stale" : reader.is_stale(),
+            "first_indexed_block" : db.get_first_scanned_block().unwrap(),
             "last_indexed_block" : db.get_next_block_to_scan().unwrap(),
             "blocks": blocks,
             "has_next_page": pg.next_page.is_some(),
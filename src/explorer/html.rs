@@ -8,10 +8,12 @@ use ethabi;
 use super::error::Result;
 
 use super::super::eth::types::InternalTx;
+use super::super::eth::{PoolStatus, PoolTx};
 use super::super::state::GlobalState;
-use super::super::eth::contract::ContractParser;
+use super::super::eth::contract::{ContractParser, RevertInfo, panic_reason};
 
 const DATETIME_FORMAT : &str = "%Y-%m-%d %H:%M:%S";
+const DATE_FORMAT : &str = "%Y-%m-%d";
 
 lazy_static! {
     static ref GWEI: U256 = U256::from_dec_str("1000000000").unwrap();
@@ -139,20 +141,16 @@ impl<'a> HtmlRender<'a> {
         format!("{}",dt.format(DATETIME_FORMAT))
     }
 
+    /// render a day, as days since 1970
+    pub fn day(&self, day : u64) -> String {
+        let dt = Utc.timestamp((day * 86400) as i64, 0);
+        format!("{}",dt.format(DATE_FORMAT))
+    }
+
     /// render a transaction
     pub fn tx(&mut self,tx: &Transaction, rcpt: &Option<TransactionReceipt>) -> Result<serde_json::Value> {
         
-        let shortdata = if let Some(to) = tx.to {
-            if self.register_contract(&to)? {
-                let callinfo = self.parser.tx_funcparams(&to, &tx.input.0,false)?;
-                callinfo.func.to_string()
-            } else {
-                tx.input.0.to_hex::<String>()
-                .chars().take(8).collect::<String>()
-            }
-        } else {
-            String::from("")
-        };
+        let shortdata = self.shortdata(&tx.to, &tx.input.0)?;
 
         let (to_link,to_label) = if let Some(to) = tx.to {             
             (self.addr(&to),"")
@@ -175,6 +173,55 @@ impl<'a> HtmlRender<'a> {
         }))
     }
 
+    /// render the function called, or the selector if the contract is not known
+    fn shortdata(&mut self, to: &Option<Address>, input: &[u8]) -> Result<String> {
+        if let Some(to) = to {
+            if self.register_contract(&to)? {
+                let callinfo = self.parser.tx_funcparams(&to, input,false)?;
+                Ok(callinfo.func.to_string())
+            } else {
+                Ok(input.to_hex::<String>()
+                .chars().take(8).collect::<String>())
+            }
+        } else {
+            Ok(String::from(""))
+        }
+    }
+
+    /// render the reason why a transaction in the pool is not mined yet,
+    ///   base_fee is the one of the last block
+    pub fn pool_status(&self, ptx: &PoolTx, base_fee: &Option<U256>) -> String {
+        // the gas price of the pending dynamic fee transactions is the max fee
+        match ptx.status {
+            PoolStatus::Pending => match base_fee {
+                Some(base_fee) if ptx.tx.gas_price < *base_fee => format!(
+                    "pending, fee below the base fee {}", self.gwei(base_fee,false)),
+                _ => String::from("pending"),
+            },
+            PoolStatus::NonceGap(from, to) if from == to => format!("waiting for nonce {}", from),
+            PoolStatus::NonceGap(from, to) => format!("waiting for nonces {}-{}", from, to),
+            PoolStatus::Queued => String::from("queued, not executable"),
+        }
+    }
+
+    /// render a transaction in the pool
+    pub fn pool_tx(&mut self, ptx: &PoolTx, base_fee: &Option<U256>) -> Result<serde_json::Value> {
+        let tx = &ptx.tx;
+        let shortdata = self.shortdata(&tx.to, &tx.input.0)?;
+
+        Ok(json!({
+            "tx"            : self.txid(&tx.hash),
+            "from"          : self.addr(&tx.from),
+            "nonce"         : tx.nonce.low_u64(),
+            "to_link"       : self.addr_or(&tx.to,"New contract"),
+            "shortdata"     : shortdata,
+            "value"         : self.ether(&tx.value,true),
+            "gas_price"     : self.gwei(&tx.gas_price,true),
+            "queued"        : ptx.status != PoolStatus::Pending,
+            "status"        : self.pool_status(ptx, base_fee),
+        }))
+    }
+
     /// render an address that is a contract
     fn addr_newcontract(&self, addr: &Address) -> TextWithLink {
         let mut twl = self.addr(&addr);
@@ -186,25 +233,23 @@ impl<'a> HtmlRender<'a> {
     fn addr_to(&self, to: &Option<Address>, contract: &Option<Address>) -> TextWithLink {
         if let Some(to) = to {
             self.addr(&to)
+        } else if let Some(contract) = contract {
+            self.addr_newcontract(&contract)
         } else {
-            self.addr_newcontract(&contract.unwrap())
+            TextWithLink::blank()
         }
     }
 
     /// render an internal transaction
     pub fn tx_itx(&mut self,tx: &Transaction, itx: &InternalTx) -> Result<serde_json::Value> {
-        
-        let shortdata = if let Some(to) = itx.to {
-            if self.register_contract(&to)? {
-                let callinfo = self.parser.tx_funcparams(&to, &itx.input,false)?;
-                callinfo.func.to_string()
-            } else {
-                itx.input.to_hex::<String>()
-                .chars().take(8).collect::<String>()
-            }
+
+        let revert = if itx.error.is_some() && !itx.output.is_empty() {
+            Some(self.revert_reason(&itx.to, &itx.output)?)
         } else {
-            String::from("")
+            None
         };
+        
+        let shortdata = self.shortdata(&itx.to, &itx.input)?;
 
         Ok(json!({
             "type"          : "int",
@@ -213,7 +258,12 @@ impl<'a> HtmlRender<'a> {
             "from"          : self.addr(&itx.from),
             "to_link"       : self.addr_to(&itx.to,&itx.contract),
             "shortdata"     : shortdata,
-            "value"         : self.ether(&itx.value,true)
+            "value"         : self.ether(&itx.value,true),
+            "call_type"     : itx.call_type,
+            "revert"        : revert,
+            "indent"        : itx.depth.saturating_sub(1),
+            "gas_used"      : itx.gas_used.low_u64(),
+            "error"         : itx.error,
         }))
     }
 
@@ -264,6 +314,27 @@ impl<'a> HtmlRender<'a> {
         }
     }
 
+    /// render the reason of a reverted call
+    pub fn revert_reason(&mut self, addr: &Option<Address>, data: &[u8]) -> Result<String> {
+        if let Some(addr) = addr {
+            self.register_contract(addr)?;
+        }
+        let reason = match self.parser.revert_reason(addr.as_ref(), data)? {
+            RevertInfo::Empty => String::from("reverted without reason"),
+            RevertInfo::Error(reason) => format!("Error(\"{}\")", reason),
+            RevertInfo::Panic(code) => format!("Panic(0x{:x}): {}", code, panic_reason(code)),
+            RevertInfo::Custom(callinfo) => {
+                let params = callinfo.params.iter()
+                    .map(|(name,value)| format!("{}={}", name, self.abi_token(value)))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{}({})", callinfo.func, params)
+            }
+            RevertInfo::Unknown(data) => format!("0x{}", data.to_hex::<String>()),
+        };
+        Ok(reason)
+    }
+
     /// render a token (basic ethereum type)
     fn abi_token(&self, token : &ethabi::Token) -> String {
         match token {
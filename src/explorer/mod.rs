@@ -1,11 +1,15 @@
 mod address;
 mod block;
 mod error;
+mod genesis;
 mod home;
 mod html;
 mod tx;
 mod neb;
+mod pending;
+mod signers;
 mod utils;
 mod server;
+mod tests;
 
 pub use self::server::start_explorer;
\ No newline at end of file
@@ -41,7 +41,7 @@ pub fn render(
                     "gas_used"   : format!("{}%",gas_used_p), 
                     "gas_limit"  : format!("{}.{}M",gas_limit/10,gas_limit%10)
                 }));
-            } else {
+            } else if !reader.is_stale() {
                 return Err(Error::Unexpected);
             }
         }
@@ -51,6 +51,8 @@ pub fn render(
         "neb.handlebars",
         &json!({
             "ui_title" : ge.cfg.ui_title,
+            "stale" : reader.is_stale(),
+            "first_indexed_block" : db.get_first_scanned_block().unwrap(),
             "last_indexed_block" : db.get_next_block_to_scan().unwrap(),
             "blocks": blocks,
             "has_next_page": pg.next_page.is_some(),
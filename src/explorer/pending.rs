@@ -0,0 +1,46 @@
+use super::error::Result;
+use super::html::HtmlRender;
+use super::utils;
+
+use super::super::state::GlobalState;
+use super::super::eth::BlockchainReader;
+
+/// render the transaction pool page
+pub fn render(
+    ge: &GlobalState,
+    page_no : u64,
+) -> Result<String> {
+
+    let mut hr = HtmlRender::new(&ge);
+    let reader = BlockchainReader::new(&ge);
+    let hb = &ge.hb;
+
+    let pool = reader.pool_content(None)?.unwrap_or_default();
+    let (count, queued_count) = (pool.count(), pool.queued_count());
+    let base_fee = reader.block_ext(reader.current_block_number()?)?
+        .and_then(|block| block.base_fee_per_gas);
+
+    // only the senders of the page need their nonce to find the gaps
+    let pg = utils::paginate(count as u64,25,page_no);
+    let mut txs = Vec::new();
+    if pg.from <= pg.to {
+        for ptx in reader.pool_txs(pool, pg.from as usize, (pg.to-pg.from) as usize)? {
+            txs.push(hr.pool_tx(&ptx, &base_fee)?);
+        }
+    }
+
+    Ok(hb.render(
+        "pending.handlebars",
+        &json!({
+            "ui_title" : ge.cfg.ui_title,
+            "stale" : reader.is_stale(),
+            "pool" : txs,
+            "pending_count" : count - queued_count,
+            "queued_count" : queued_count,
+            "has_next_page": pg.next_page.is_some(),
+            "next_page": pg.next_page.unwrap_or(0),
+            "has_prev_page": pg.prev_page.is_some(),
+            "prev_page": pg.prev_page.unwrap_or(0),
+        }),
+    )?)
+}
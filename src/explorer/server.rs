@@ -69,6 +69,23 @@ fn get_object(request: &Request,ge: &GlobalState, id: &str) -> Response {
             Ok(html) => html,
             Err(err) => error_page(format!("Error: {:?}", err).as_str())
         })
+    } else if id == "genesis" {
+        let page_no = request.get_param("p").unwrap_or_else(|| "0".to_string()).parse::<u64>().unwrap();
+        Response::html(match super::genesis::render(&ge,page_no) {
+            Ok(html) => html,
+            Err(err) => error_page(format!("Error: {:?}", err).as_str())
+        })
+    } else if id == "pending" {
+        let page_no = request.get_param("p").unwrap_or_else(|| "0".to_string()).parse::<u64>().unwrap();
+        Response::html(match super::pending::render(&ge,page_no) {
+            Ok(html) => html,
+            Err(err) => error_page(format!("Error: {:?}", err).as_str())
+        })
+    } else if id == "signers" {
+        Response::html(match super::signers::render(&ge) {
+            Ok(html) => html,
+            Err(err) => error_page(format!("Error: {:?}", err).as_str())
+        })
     } else if let Some(id) = Id::from(&id) {
         let page_no = request.get_param("p").unwrap_or_else(|| "0".to_string()).parse::<u64>().unwrap();
         let html = match id {
@@ -85,6 +102,29 @@ fn get_object(request: &Request,ge: &GlobalState, id: &str) -> Response {
     }
 }
 
+fn get_tx_state(ge: &GlobalState, id: &str) -> Response {
+    if let Some(Id::Tx(txid)) = Id::from(&id) {
+        Response::html(match super::tx::render_state(&ge,txid) {
+            Ok(html) => html,
+            Err(err) => error_page(format!("Error: {:?}", err).as_str())
+        })
+    } else {
+        Response::html(error_page("Not found"))
+    }
+}
+
+fn get_tx_trace(request: &Request, ge: &GlobalState, id: &str) -> Response {
+    let page_no = request.get_param("p").unwrap_or_else(|| "0".to_string()).parse::<u64>();
+    if let (Some(Id::Tx(txid)), Ok(page_no)) = (Id::from(&id), page_no) {
+        Response::html(match super::tx::render_trace(&ge,txid,page_no) {
+            Ok(html) => html,
+            Err(err) => error_page(format!("Error: {:?}", err).as_str())
+        })
+    } else {
+        Response::html(error_page("Not found"))
+    }
+}
+
 fn post_contract(
     ge: &GlobalState,
     id: &str,
@@ -98,7 +138,24 @@ fn post_contract(
     if let Some(Id::Addr(addr)) = Id::from(&id) {
         let reader = BlockchainReader::new(&ge);
 
-        let code = reader.current_code(&addr).expect("failed to read contract code").0;
+        let code = match reader.current_code(&addr).expect("failed to read contract code") {
+            Some(code) => code.0,
+            None => return Response::html(error_page("node not available, cannot verify contract")),
+        };
+
+        let (abi, storage_layout, srcmap, srcmap_file) = if ge.cfg.solc_bypass && contract_compiler==ONLY_ABI {
+            verify_abi(contract_source).expect("cannot verify abi");
+            (contract_source.to_string(), None, None, 0)
+        } else {
+            let compiled = compile_and_verify(&ge.cfg,
+                &contract_source,
+                &contract_name,
+                &contract_compiler,
+                contract_optimized,
+                &code
+            ).expect("cannot verify contract code");
+            (compiled.abi, compiled.storage_layout.map(|layout| layout.to_string()), compiled.srcmap, compiled.srcmap_file)
+        };
 
         let contractentry = db::Contract{
             source : contract_source.to_string(),
@@ -106,18 +163,10 @@ fn post_contract(
             optimized: contract_optimized,
             name : contract_name.to_string(),
             constructor : Vec::new(),
-            abi : if ge.cfg.solc_bypass && contract_compiler==ONLY_ABI {
-                verify_abi(contract_source).expect("cannot verify abi");
-                contract_source.to_string()
-            } else {
-                compile_and_verify(&ge.cfg,
-                    &contract_source,
-                    &contract_name,
-                    &contract_compiler,
-                    contract_optimized,
-                    &code
-                ).expect("cannot verify contract code")
-            }
+            abi,
+            storage_layout,
+            srcmap,
+            srcmap_file,
         };
         ge.db.set_contract(&addr,&contractentry).expect("cannot update db");
 
@@ -143,6 +192,12 @@ pub fn start_explorer(gs: Arc<GlobalState>) {
         (GET)  (/{id: String}) => {
             get_object(&request,&gs,&id)
         },
+        (GET)  (/{id: String}/state) => {
+            get_tx_state(&gs,&id)
+        },
+        (GET)  (/{id: String}/trace) => {
+            get_tx_trace(&request,&gs,&id)
+        },
         (POST) (/{id: String}/contract) => {
             let data = try_or_400!(post_input!(request, {
                 contract_source: String,
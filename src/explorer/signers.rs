@@ -0,0 +1,120 @@
+use web3::types::Address;
+
+use super::error::Result;
+use super::html::HtmlRender;
+
+use super::super::db::AppDB;
+use super::super::state::GlobalState;
+use super::super::eth::BlockchainReader;
+
+/// days shown in the sealing charts
+const CHART_DAYS : u64 = 14;
+
+/// seal violations shown
+const MAX_VIOLATIONS : usize = 50;
+
+/// day of a clique block, from its seal since the blocks are only stored
+///   with db_store_tx
+pub fn last_day(db: &AppDB, reader: &BlockchainReader, blockno: u64) -> Result<u64> {
+    if let Some(seal) = db.get_seal(blockno)? {
+        return Ok(seal.day);
+    }
+    Ok(reader.block(blockno)?.map_or(0, |block| block.timestamp.low_u64() / 86400))
+}
+
+/// render the clique signers page
+pub fn render(
+    ge: &GlobalState,
+) -> Result<String> {
+
+    let hr = HtmlRender::new(&ge);
+    let reader = BlockchainReader::new(&ge);
+    let db = &ge.db;
+    let hb = &ge.hb;
+
+    let mut proposals = Vec::new();
+    let mut snapshot_block = None;
+    let mut sealers = Vec::new();
+
+    if let Some(snapshot) = db.get_clique_snapshot()? {
+        let needed = snapshot.signers.len() / 2 + 1;
+        for (target, authorize, voters) in snapshot.proposals() {
+            proposals.push(json!({
+                "target"    : hr.addr(&target),
+                "authorize" : authorize,
+                "tally"     : format!("{}/{}", voters.len(), needed),
+                "voters"    : voters.iter().map(|v| hr.addr(v)).collect::<Vec<_>>(),
+            }));
+        }
+        snapshot_block = Some(hr.blockno(snapshot.number));
+
+        // current signers first, then the ones that sealed in the past
+        let mut addrs : Vec<Address> = snapshot.signers.clone();
+        for sealer in db.get_sealers()? {
+            if !snapshot.is_signer(&sealer) {
+                addrs.push(sealer);
+            }
+        }
+
+        // charts end at the day of the last indexed block
+        let last_day = last_day(db, &reader, snapshot.number)?;
+        let first_day = (last_day + 1).saturating_sub(CHART_DAYS);
+
+        let mut days = Vec::new();
+        for addr in &addrs {
+            days.push(db.get_sealer_days(addr, first_day, last_day + 1)?);
+        }
+        let max_day = days.iter().flatten().cloned().max().unwrap_or(0).max(1);
+
+        for (addr, days) in addrs.iter().zip(days) {
+            let stats = db.get_sealer_stats(addr)?;
+            let chart : Vec<_> = days.iter().enumerate()
+                .map(|(i, count)| json!({
+                    "date"   : hr.day(first_day + i as u64),
+                    "count"  : count,
+                    "height" : 100 * count / max_day,
+                }))
+                .collect();
+            sealers.push(json!({
+                "signer"      : hr.addr(addr),
+                "authorized"  : snapshot.is_signer(addr),
+                "sealed"      : stats.sealed,
+                "in_turn"     : stats.in_turn,
+                "out_of_turn" : stats.out_of_turn,
+                "missed"      : stats.missed,
+                "last_block"  : stats.last_block.map(|n| hr.blockno(n)),
+                "chart"       : chart,
+            }));
+        }
+    }
+
+    let changes : Vec<_> = db.get_signer_changes()?.into_iter()
+        .map(|(blockno, change)| json!({
+            "block"  : hr.blockno(blockno),
+            "signer" : hr.addr(&change.signer),
+            "added"  : change.added,
+        }))
+        .collect();
+
+    let violations : Vec<_> = db.get_seal_violations(MAX_VIOLATIONS)?.into_iter()
+        .map(|(blockno, violation)| json!({
+            "block"     : hr.blockno(blockno),
+            "violation" : violation.to_string(),
+        }))
+        .collect();
+
+    Ok(hb.render(
+        "signers.handlebars",
+        &json!({
+            "ui_title" : ge.cfg.ui_title,
+            "stale" : reader.is_stale(),
+            "has_snapshot" : snapshot_block.is_some(),
+            "snapshot_block" : snapshot_block,
+            "sealers" : sealers,
+            "proposals" : proposals,
+            "changes" : changes,
+            "verify_seals" : ge.cfg.clique_verify_seals.unwrap_or(false),
+            "violations" : violations,
+        }),
+    )?)
+}
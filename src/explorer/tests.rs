@@ -0,0 +1,27 @@
+#[cfg(test)]
+mod tests {
+    use web3::types::{Address, Block, H256};
+
+    use super::super::super::db::Seal;
+    use super::super::super::eth::BlockchainReader;
+    use super::super::super::fixtures::{block_with, test_config, test_state};
+    use super::super::signers::last_day;
+
+    #[test]
+    fn test_signers_last_day_without_stored_blocks() {
+        let mut cfg = test_config();
+        cfg.db_store_tx = false;
+        let gs = test_state(cfg);
+        let reader = BlockchainReader::new(&gs);
+
+        // the block is not stored, the day comes from the seal
+        let b : Block<H256> = block_with(7, json!({ "timestamp": format!("0x{:x}", 20 * 86400) }));
+        let mut writer = gs.db.writer();
+        writer.add_block(&b).unwrap();
+        writer.add_seal(7, &Seal { signer: Address::from(1), in_turn: true, missed: None, day: 20 }).unwrap();
+        writer.commit().unwrap();
+
+        assert!(gs.db.get_block(7).unwrap().is_none());
+        assert_eq!(20, last_day(&gs.db, &reader, 7).unwrap());
+    }
+}
@@ -1,9 +1,15 @@
-use web3::types::H256;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use web3::types::{Address, H256};
 
 use super::error::*;
 use super::html::*;
+use super::utils;
 
+use super::super::eth;
 use super::super::eth::{BlockchainReader};
+use super::super::eth::contract::{storage_slot_names, SourceMap};
+use super::super::eth::geth::web3::DbgAccountState;
+use super::super::eth::types::{hex_to_addr, hex_to_u256, u256_to_addr};
 use super::super::state::GlobalState;
 
 /// render the transaction page
@@ -22,10 +28,24 @@ pub fn render(
         let mut gas_used = String::from("");
         let mut contract_address = TextWithLink::blank();
         let mut status = String::from("");
+        let mut revert = None;
+        let mut burned_fee = None;
+
+        // typed transaction fields, the effective gas price of legacy
+        //   transactions is the gas price
+        let ext = reader.tx_ext(&txid)?.unwrap_or_default();
+        let blockno = tx.block_number.map(|blockno| blockno.low_u64());
+        let base_fee = match blockno {
+            Some(blockno) => reader.block_ext(blockno)?.and_then(|block| block.base_fee_per_gas),
+            None => None,
+        };
         
         if let Some(receipt) = receipt {
 
             cumulative_gas_used = format!("{}", receipt.cumulative_gas_used.low_u64());
+            if let Some(base_fee) = base_fee {
+                burned_fee = Some(hr.ether(&ext.burned_fee(&base_fee, &receipt.gas_used.unwrap()),false));
+            }
             gas_used = format!("{}", receipt.gas_used.unwrap().low_u64());
             
             if let Some(contract) = receipt.contract_address {
@@ -37,6 +57,13 @@ pub fn render(
                 |x| if x.as_u64() == 1 { "Success".to_string() } else { "Failed".to_string() }
             );  
 
+            // decode the reason of failed transactions
+            if receipt.status.map(|x| x.as_u64() == 0).unwrap_or(false) {
+                if let Some(data) = reader.revert_data(&tx)? {
+                    revert = Some(hr.revert_reason(&tx.to, &data)?);
+                }
+            }
+
             for (_, log) in receipt.logs.into_iter().enumerate() {
                 
                 let mut txt = Vec::new();
@@ -63,6 +90,20 @@ pub fn render(
             }
         }
 
+        // transactions not mined yet are in the pool, show why
+        if blockno.is_none() {
+            let pool_content = reader.pool_content(Some(&tx.from))?.unwrap_or_default();
+            let pool_txs = reader.pool_txs(pool_content, 0, std::usize::MAX)?;
+            status = match pool_txs.iter().find(|ptx| ptx.tx.hash == tx.hash) {
+                Some(ptx) => {
+                    let last_base_fee = reader.block_ext(reader.current_block_number()?)?
+                        .and_then(|block| block.base_fee_per_gas);
+                    hr.pool_status(ptx, &last_base_fee)
+                }
+                None => String::from("pending"),
+            };
+        }
+
         // log_to_string
         let mut input: Vec<String> = Vec::new();
         if let Some(to) = tx.to {
@@ -76,29 +117,64 @@ pub fn render(
             input.extend_from_slice(&inputvec);
         }
 
-        // internal transactions
-        let itxs : Result<Vec<_>>= reader.itx(&tx)?
-            .into_iter()
-            .map(|itx| hr.tx_itx(&tx,&itx))
+        let access_list : Vec<_> = ext.access_list.iter()
+            .flatten()
+            .map(|item| json!({
+                "address" : hr.addr(&item.address),
+                "storage_keys" : item.storage_keys.iter().map(|key| format!("0x{:x}",key)).collect::<Vec<_>>(),
+            }))
+            .collect();
+        let blob_hashes : Vec<_> = ext.blob_versioned_hashes.iter()
+            .flatten()
+            .map(|hash| format!("0x{:x}",hash))
             .collect();
 
+        // transactions in blocks below the confirmations depth are not indexed yet
+        let unconfirmed = match blockno {
+            Some(blockno) => reader.is_unconfirmed(blockno,reader.current_block_number()?),
+            None => false,
+        };
+
+        // internal transactions, only for mined ones
+        let itxs : Result<Vec<_>> = if blockno.is_some() {
+            reader.itx(&tx)?
+                .into_iter()
+                .map(|itx| hr.tx_itx(&tx,&itx))
+                .collect()
+        } else {
+            Ok(Vec::new())
+        };
+
         // render page
         Ok(hb.render(
             "tx.handlebars",
             &json!({
             "ui_title"            : ge.cfg.ui_title,
+            "stale"               : reader.is_stale(),
             "txhash"              : format!("0x{:x}",txid),
             "from"                : hr.addr(&tx.from),
             "tonewcontract"       : tx.to.is_none(),
             "to"                  : hr.addr_or(&tx.to,"New contract"),
             "value"               : hr.ether(&tx.value,true),
-            "block"               : hr.blockno(tx.block_number.unwrap().low_u64()),
+            "block"               : blockno.map(|blockno| hr.blockno(blockno)),
+            "unconfirmed"         : unconfirmed,
             "gas"                 : tx.gas.low_u64(),
             "gas_price"           : hr.gwei(&tx.gas_price,false),
+            "tx_type"             : ext.type_name(),
+            "max_fee"             : ext.max_fee_per_gas.map(|fee| hr.gwei(&fee,false)),
+            "max_priority_fee"    : ext.max_priority_fee_per_gas.map(|fee| hr.gwei(&fee,false)),
+            "effective_gas_price" : hr.gwei(&ext.effective_gas_price.unwrap_or(tx.gas_price),false),
+            "burned_fee"          : burned_fee,
+            "max_fee_per_blob_gas": ext.max_fee_per_blob_gas.map(|fee| hr.gwei(&fee,false)),
+            "blob_gas_used"       : ext.blob_gas_used.map(|gas| gas.low_u64()),
+            "blob_gas_price"      : ext.blob_gas_price.map(|price| hr.gwei(&price,false)),
+            "blob_hashes"         : blob_hashes,
+            "access_list"         : access_list,
             "cumulative_gas_used" : cumulative_gas_used,
             "gas_used"            : gas_used,
             "contract_address"    : contract_address,
             "status"              : status,
+            "revert"              : revert,
             "input"               : input,
             "logs"                : logs,
             "itxs"                : itxs?,
@@ -107,4 +183,213 @@ pub fn render(
     } else {
         Err(Error::NotFound)
     }
-}
\ No newline at end of file
+}
+/// render the balance, nonce, code and storage changes made by a transaction
+pub fn render_state(
+    ge: &GlobalState,
+    txid: H256) -> Result<String> {
+
+    let hr = HtmlRender::new(&ge); 
+    let reader = BlockchainReader::new(&ge);
+    let hb = &ge.hb;
+    let db = &ge.db;
+
+    let diff = match reader.state_diff(txid)? {
+        Some(diff) => diff,
+        None => return Err(Error::NotFound),
+    };
+
+    // accounts in pre and not in post are deleted, in post and not in pre are created
+    let addrs : BTreeSet<&String> = diff.pre.keys().chain(diff.post.keys()).collect();
+    let empty = DbgAccountState::default();
+
+    let mut accounts = Vec::new();
+    for addr_hex in addrs {
+        let addr = hex_to_addr(addr_hex).map_err(eth::Error::from)?;
+        let pre = diff.pre.get(addr_hex).unwrap_or(&empty);
+        let post = diff.post.get(addr_hex).unwrap_or(&empty);
+
+        // only the changed fields are present in post
+        let ether = |v: &Option<String>| -> Result<String> {
+            match v {
+                Some(v) => Ok(hr.ether(&hex_to_u256(v).map_err(eth::Error::from)?,false)),
+                None => Ok(String::from("-")),
+            }
+        };
+        let balance = if post.balance.is_some() {
+            Some(json!({ "from" : ether(&pre.balance)?, "to" : ether(&post.balance)? }))
+        } else {
+            None
+        };
+        let nonce = post.nonce.map(|to| json!({
+            "from" : pre.nonce.map(|n| n.to_string()).unwrap_or_else(|| String::from("-")),
+            "to" : to,
+        }));
+        let code_len = |code: &Option<String>| code.as_ref().map(|c| c.len().saturating_sub(2) / 2).unwrap_or(0);
+        let code = if post.code.is_some() {
+            Some(json!({ "from" : code_len(&pre.code), "to" : code_len(&post.code) }))
+        } else {
+            None
+        };
+
+        // name the slots if the storage layout of the contract is known
+        let slot_names = match db.get_contract(&addr)?.and_then(|c| c.storage_layout) {
+            Some(layout) => storage_slot_names(&layout)?,
+            None => HashMap::new(),
+        };
+        let no_storage = BTreeMap::new();
+        let pre_storage = pre.storage.as_ref().unwrap_or(&no_storage);
+        let post_storage = post.storage.as_ref().unwrap_or(&no_storage);
+        let slots : BTreeSet<&String> = pre_storage.keys().chain(post_storage.keys()).collect();
+        let mut storage = Vec::new();
+        for slot in slots {
+            let name = hex_to_u256(slot).ok()
+                .and_then(|slot| slot_names.get(&slot).cloned())
+                .unwrap_or_default();
+            storage.push(json!({
+                "slot" : slot,
+                "name" : name,
+                "from" : pre_storage.get(slot).map(String::as_str).unwrap_or("0x0"),
+                "to"   : post_storage.get(slot).map(String::as_str).unwrap_or("0x0"),
+            }));
+        }
+
+        accounts.push(json!({
+            "addr"    : hr.addr(&addr),
+            "created" : !diff.pre.contains_key(addr_hex),
+            "deleted" : !diff.post.contains_key(addr_hex),
+            "balance" : balance,
+            "nonce"   : nonce,
+            "code"    : code,
+            "storage" : storage,
+        }));
+    }
+
+    Ok(hb.render(
+        "txstate.handlebars",
+        &json!({
+        "ui_title" : ge.cfg.ui_title,
+        "stale"    : reader.is_stale(),
+        "txhash"   : format!("0x{:x}",txid),
+        "accounts" : accounts,
+        }),
+    )?)
+}
+
+/// get the source map and the source of a verified contract
+fn load_source_map(ge: &GlobalState, reader: &BlockchainReader, addr: &Address) -> Result<Option<(SourceMap,String)>> {
+    if let Some(contract) = ge.db.get_contract(addr)? {
+        if let (Some(srcmap), Some(code)) = (contract.srcmap, reader.current_code(addr)?) {
+            return Ok(Some((SourceMap::parse(&srcmap, &code.0, contract.srcmap_file), contract.source)));
+        }
+    }
+    Ok(None)
+}
+
+/// render the opcode-level trace of a transaction
+pub fn render_trace(
+    ge: &GlobalState,
+    txid: H256,
+    page_no: u64) -> Result<String> {
+
+    let hr = HtmlRender::new(&ge); 
+    let reader = BlockchainReader::new(&ge);
+    let hb = &ge.hb;
+
+    let tx = match reader.tx(txid)? {
+        Some((tx,_)) => tx,
+        None => return Err(Error::NotFound),
+    };
+    let trace = match reader.struct_logs(txid)? {
+        Some(trace) => trace,
+        None => return Err(Error::NotFound),
+    };
+    let logs = &trace.struct_logs;
+    let pg = utils::paginate(logs.len() as u64,50,page_no);
+
+    // the address of the code executed at each depth, None for contract creations
+    let mut code_addrs : Vec<Option<Address>> = vec![tx.to];
+    let mut source_maps : HashMap<Address,Option<(SourceMap,String)>> = HashMap::new();
+
+    let mut steps = Vec::new();
+    for (i, log) in logs.iter().enumerate().take(pg.to as usize) {
+
+        let prev = if i > 0 { Some(&logs[i-1]) } else { None };
+
+        // when entering a call the code address was the second word of the stack
+        if let Some(prev) = prev {
+            if log.depth > prev.depth {
+                let called = match prev.op.as_str() {
+                    "CALL" | "CALLCODE" | "DELEGATECALL" | "STATICCALL" =>
+                        prev.stack.iter().rev().nth(1)
+                            .and_then(|word| hex_to_u256(word).ok())
+                            .map(|word| u256_to_addr(&word)),
+                    _ => None,
+                };
+                code_addrs.push(called);
+            }
+        }
+        code_addrs.truncate(log.depth.max(1) as usize);
+
+        if (i as u64) < pg.from {
+            continue;
+        }
+
+        // memory and storage changes since the previous step in the same call
+        let prev = prev.filter(|prev| prev.depth == log.depth);
+        let memory : Vec<String> = log.memory.iter().enumerate()
+            .filter(|(n, word)| prev.map(|p| p.memory.get(*n) != Some(*word)).unwrap_or(true))
+            .map(|(n, word)| format!("{:#x}: {}", n*32, word))
+            .collect();
+        let storage : Vec<String> = log.storage.iter()
+            .filter(|(slot, value)| prev.map(|p| p.storage.get(*slot) != Some(*value)).unwrap_or(true))
+            .map(|(slot, value)| format!("{}: {}", slot, value))
+            .collect();
+
+        // source line, if the executed contract has been verified
+        let mut source = None;
+        if let Some(Some(addr)) = code_addrs.last() {
+            if !source_maps.contains_key(addr) {
+                source_maps.insert(*addr, load_source_map(ge, &reader, addr)?);
+            }
+            if let Some((map, src)) = &source_maps[addr] {
+                source = map.source_line(src, log.pc);
+            }
+        }
+
+        steps.push(json!({
+            "step"     : i,
+            "pc"       : log.pc,
+            "op"       : log.op,
+            "gas"      : log.gas,
+            "gas_cost" : log.gas_cost,
+            "depth"    : log.depth,
+            "indent"   : log.depth.saturating_sub(1),
+            "stack"    : log.stack.iter().rev().take(3).collect::<Vec<_>>(),
+            "memory"   : memory,
+            "storage"  : storage,
+            "error"    : log.error,
+            "source_line" : source.as_ref().map(|(line,_)| *line),
+            "source"   : source.map(|(_,text)| text),
+        }));
+    }
+
+    Ok(hb.render(
+        "txtrace.handlebars",
+        &json!({
+        "ui_title"      : ge.cfg.ui_title,
+        "stale"         : reader.is_stale(),
+        "txhash"        : format!("0x{:x}",txid),
+        "to"            : hr.addr_or(&tx.to,"New contract"),
+        "failed"        : trace.failed,
+        "gas"           : trace.gas,
+        "return_value"  : trace.return_value,
+        "steps_count"   : logs.len(),
+        "steps"         : steps,
+        "has_next_page" : pg.next_page.is_some(),
+        "next_page"     : pg.next_page.unwrap_or(0),
+        "has_prev_page" : pg.prev_page.is_some(),
+        "prev_page"     : pg.prev_page.unwrap_or(0),
+        }),
+    )?)
+}
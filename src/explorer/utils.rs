@@ -1,6 +1,6 @@
-use web3::types::{Address,Block,H160};
+use web3::types::{Address,Block,H160,U256};
 
-use super::super::bootstrap::{Config,GETH_CLIQUE,GETH_POW,GETH_AUTO};
+use super::super::bootstrap::{Config,GETH_CLIQUE,GETH_POW,GETH_AUTO,GETH_POS};
 use super::super::eth::geth::clique;
 
 #[derive(Debug)]
@@ -32,11 +32,14 @@ pub fn paginate( max : u64, page_size : u64, page_no : u64 ) -> Pagination {
     Pagination{from,to,prev_page,next_page}
 }
 
-/// get the author of a block
+/// get the author of a block, post-merge blocks have zero difficulty and
+///   the author is the fee recipient
 pub fn block_author<T>(cfg: &Config, block: &Block<T>) -> Address {
     
     let client = if cfg.web3_client == GETH_AUTO {
-        if block.author == H160::default() {
+        if block.difficulty == U256::zero() {
+            GETH_POS
+        } else if block.author == H160::default() {
             GETH_CLIQUE
         } else {
             GETH_POW
@@ -46,7 +49,7 @@ pub fn block_author<T>(cfg: &Config, block: &Block<T>) -> Address {
     };
     
     if client == GETH_CLIQUE {       
-        clique::parse_clique_header(&block).unwrap()
+        clique::parse_clique_header(&block).unwrap_or(block.author)
     } else {
         block.author
     }
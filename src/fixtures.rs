@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::iter;
+use std::sync::atomic::AtomicBool;
+use std::sync::Mutex;
+
+use handlebars::Handlebars;
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use serde::de::DeserializeOwned;
+use web3::types::{Address, Block, H2048, H256};
+
+use bootstrap::Config;
+use db::{AppDB, Options};
+use state::GlobalState;
+
+/// a random path in the temp dir for a test database
+pub fn temp_db_path() -> String {
+    let mut rng = thread_rng();
+    let chars: String = iter::repeat(())
+        .map(|()| rng.sample(Alphanumeric))
+        .take(7)
+        .collect();
+
+    let mut tmpfile = std::env::temp_dir();
+    tmpfile.push(chars);
+    tmpfile.as_os_str().to_str().expect("bad OS filename").to_string()
+}
+
+/// create an empty database in the temp dir
+pub fn temp_db(options: Options) -> AppDB {
+    AppDB::open_default(&temp_db_path(), options).expect("unable to create db")
+}
+
+/// the config of a geth node that is not running, with an empty database
+///   in the temp dir
+pub fn test_config() -> Config {
+    toml::from_str(&format!(r#"
+        ui_title = "test"
+        db_path = "{}"
+        db_store_itx = true
+        db_store_tx = true
+        db_store_addr = true
+        db_store_neb = true
+        web3_url = "http://127.0.0.1:1"
+        web3_client = "geth"
+        web3_itx = false
+        scan = true
+        bind = "127.0.0.1:0"
+        solc_bypass = false
+    "#, temp_db_path())).unwrap()
+}
+
+/// create the state with the config, opening its database
+pub fn test_state(cfg: Config) -> GlobalState {
+    let db = AppDB::open_default(&cfg.db_path, Options {
+        store_itx  : cfg.db_store_itx,
+        store_tx   : cfg.db_store_tx,
+        store_addr : cfg.db_store_addr,
+        store_neb  : cfg.db_store_neb,
+    }).expect("unable to create db");
+
+    GlobalState {
+        stop_signal : AtomicBool::new(false),
+        db,
+        cfg,
+        hb : Handlebars::new(),
+        named_address : HashMap::new(),
+        reward_schedule : None,
+        last_trace : Mutex::new(None),
+    }
+}
+
+/// a header without transactions, the hash is the number and the parent hash
+///   the previous number
+pub fn block<T: DeserializeOwned>(number: u64) -> Block<T> {
+    block_with(number, json!({}))
+}
+
+/// a header without transactions, replacing the default fields with the
+///   ones in the json object
+pub fn block_with<T: DeserializeOwned>(number: u64, fields: serde_json::Value) -> Block<T> {
+    let mut block = json!({
+        "hash": H256::from(number),
+        "parentHash": H256::from(number.saturating_sub(1)),
+        "sha3Uncles": H256::zero(),
+        "miner": Address::zero(),
+        "stateRoot": H256::zero(),
+        "transactionsRoot": H256::zero(),
+        "receiptsRoot": H256::zero(),
+        "number": format!("0x{:x}", number),
+        "gasUsed": "0x0",
+        "gasLimit": "0x47b760",
+        "extraData": "0x",
+        "logsBloom": H2048::default(),
+        "timestamp": "0x0",
+        "difficulty": "0x1",
+        "totalDifficulty": "0x0",
+        "sealFields": [],
+        "uncles": [],
+        "transactions": [],
+        "size": "0x0",
+    });
+    if let (Some(block), serde_json::Value::Object(fields)) = (block.as_object_mut(), fields) {
+        block.extend(fields);
+    }
+    serde_json::from_value(block).unwrap()
+}
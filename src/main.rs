@@ -35,6 +35,8 @@ mod scrapper;
 mod state;
 mod bootstrap;
 mod eth;
+#[cfg(test)]
+mod fixtures;
 
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
@@ -57,6 +59,26 @@ struct Opt {
     /// Timestamp (sec, ms, ns, none)
     #[structopt(short = "cfg", long = "cfg")]
     cfg: String,
+
+    /// Reindex a block range (FROM-TO) and exit
+    #[structopt(long = "reindex")]
+    reindex: Option<String>,
+
+    /// Index the missing blocks below the next block to scan and exit
+    #[structopt(long = "fill-gaps")]
+    fill_gaps: bool,
+}
+
+/// parse a FROM-TO block range
+fn parse_range(range: &str) -> Option<(u64,u64)> {
+    let mut parts = range.splitn(2,'-');
+    let from = parts.next()?.trim().parse().ok()?;
+    let to = parts.next()?.trim().parse().ok()?;
+    if from <= to {
+        Some((from,to))
+    } else {
+        None
+    }
 }
 
 fn main() {
@@ -78,10 +100,28 @@ fn main() {
     // create the (arc) global state 
     let globalstate = Arc::new(state::GlobalState::new(cfg).unwrap());
 
+    // run maintenance commands
+    if let Some(range) = &opt.reindex {
+        let (from, to) = parse_range(range)
+            .expect("invalid block range, expected FROM-TO");
+        if let Err(err) = scrapper::reindex(&globalstate, from, to) {
+            error!("Reindex failed: {:?}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if opt.fill_gaps {
+        if let Err(err) = scrapper::fill_gaps(&globalstate) {
+            error!("Filling gaps failed: {:?}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // start scrap the blockchain (if requiered)
     if globalstate.cfg.scan {
         let shared_ge_scan = globalstate.clone();
-        thread::spawn(move || scrapper::start_scrapper(&shared_ge_scan));
+        thread::spawn(move || scrapper::start_scrapper(shared_ge_scan));
     }
 
     // set the control-c handler
@@ -0,0 +1,132 @@
+use db::{BlockWriter, Seal};
+use state::{GlobalState, Web3Client};
+
+use std::sync::atomic::Ordering;
+
+use web3::futures::Future;
+use web3::types::{Block, BlockId, BlockNumber, H256, U256};
+
+use super::super::eth::geth::clique::{parse_clique_header, Snapshot, DEFAULT_EPOCH};
+use super::error::{Error, Result};
+
+/// get the clique epoch length
+fn epoch(gs: &GlobalState) -> u64 {
+    gs.cfg.clique_epoch.unwrap_or(DEFAULT_EPOCH).max(1)
+}
+
+/// get the seal of a block, the in-turn signer is the one at blockno % len in
+///   the sorted signers of the previous block
+fn seal<T>(snapshot: &Snapshot, block: &Block<T>) -> Option<Seal> {
+    let blockno = block.number.unwrap().low_u64();
+    if blockno == 0 || snapshot.signers.is_empty() {
+        return None;
+    }
+    let signer = parse_clique_header(block)?;
+    let in_turn = block.difficulty == U256::from(2);
+    let expected = snapshot.signers[(blockno % snapshot.signers.len() as u64) as usize];
+    Some(Seal {
+        signer,
+        in_turn,
+        missed : Some(expected).filter(|expected| !in_turn && *expected != signer),
+        day : block.timestamp.low_u64() / 86400,
+    })
+}
+
+/// apply the block to the snapshot, staging the changes in the signers, the
+///   block seal and, if enabled, the seal violations
+pub fn track_block<T>(gs: &GlobalState, writer: &mut BlockWriter, snapshot: &mut Snapshot, block: &Block<T>) -> Result<()> {
+    let blockno = block.number.unwrap().low_u64();
+    if gs.cfg.clique_verify_seals.unwrap_or(false) {
+        if let Some(violation) = snapshot.verify_seal(block) {
+            warn!("Clique seal violation at block {}: {}", blockno, violation);
+            writer.add_seal_violation(blockno, &violation)?;
+        }
+    }
+    if let Some(seal) = seal(snapshot, block) {
+        writer.add_seal(blockno, &seal)?;
+    }
+    for change in snapshot.apply(block, epoch(gs)) {
+        info!("Clique signer 0x{:x} {} at block {}",
+            change.signer, if change.added { "added" } else { "removed" }, blockno);
+        writer.add_signer_change(blockno, &change)?;
+    }
+    Ok(())
+}
+
+/// get a block header from the node
+fn header(wc: &Web3Client, n: u64) -> Result<Block<H256>> {
+    Ok(wc.web3.eth()
+        .block(BlockId::Number(BlockNumber::Number(n)))
+        .wait()?
+        .ok_or(Error::BlockNotFound(n))?)
+}
+
+/// get the last checkpoint block at or before the block
+fn last_checkpoint(gs: &GlobalState, blockno: u64) -> u64 {
+    blockno - blockno % epoch(gs)
+}
+
+/// get the snapshot after the block previous to next_block, replaying the
+///   blocks after the last stored snapshot, or after the last checkpoint if it
+///   is older. The snapshots of the replayed blocks are stored
+pub fn load_snapshot(gs: &GlobalState, wc: &Web3Client, next_block: u64) -> Result<Snapshot> {
+
+    let last_block = next_block.saturating_sub(1);
+    let checkpoint = last_checkpoint(gs, last_block);
+    let mut snapshot = match gs.db.get_clique_snapshot_at(last_block)? {
+        Some(snapshot) if snapshot.number == last_block => return Ok(snapshot),
+        Some(snapshot) if snapshot.number >= checkpoint => snapshot,
+        _ => {
+            // changes already stored are overwritten with the same values, the
+            //   recent signers before the checkpoint are unknown and are not verified
+            info!("Rebuilding clique signers from checkpoint {}", checkpoint);
+            Snapshot::from_checkpoint(&header(wc, checkpoint)?)
+        }
+    };
+
+    let start = snapshot.number;
+    let mut writer = gs.db.writer();
+    writer.set_clique_snapshot(&snapshot)?;
+    for n in start+1..=last_block {
+        track_block(gs, &mut writer, &mut snapshot, &header(wc, n)?)?;
+        writer.set_clique_snapshot(&snapshot)?;
+    }
+    let first_block = gs.db.get_clique_first_block()?.unwrap_or(std::u64::MAX);
+    writer.set_clique_first_block(first_block.min(start+1))?;
+    writer.commit()?;
+
+    Ok(snapshot)
+}
+
+/// track the blocks scanned backwards, from the checkpoint before start_block
+///   until the first tracked block. Seals and changes already stored are not
+///   added twice, so the replay can be interrupted and started again
+pub fn replay_backwards(gs: &GlobalState, wc: &Web3Client, start_block: u64) -> Result<()> {
+
+    // databases created before tracking the first block are tracked until
+    //   the next block to scan
+    let first_block = match gs.db.get_clique_first_block()? {
+        Some(first_block) => first_block,
+        None => gs.db.get_next_block_to_scan()?.unwrap_or(0),
+    };
+    let checkpoint = last_checkpoint(gs, start_block.saturating_sub(1));
+    if checkpoint + 1 >= first_block {
+        return Ok(());
+    }
+
+    info!("Tracking clique signers from checkpoint {} to block {}", checkpoint, first_block);
+    let mut snapshot = Snapshot::from_checkpoint(&header(wc, checkpoint)?);
+    for n in checkpoint+1..first_block {
+        if gs.stop_signal.load(Ordering::SeqCst) {
+            break;
+        }
+        let mut writer = gs.db.writer();
+        track_block(gs, &mut writer, &mut snapshot, &header(wc, n)?)?;
+        writer.set_clique_snapshot(&snapshot)?;
+        if n + 1 == first_block {
+            writer.set_clique_first_block(checkpoint+1)?;
+        }
+        writer.commit()?;
+    }
+    Ok(())
+}
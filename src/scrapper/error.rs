@@ -1,15 +1,19 @@
 use db;
+use eth;
 use std::io;
 
 #[derive(Debug)]
 pub enum Error {
     Web3(web3::Error),
     DB(db::Error),
+    Eth(eth::Error),
     FromHex(rustc_hex::FromHexError),
     EthAbi(ethabi::Error),
     SerdeJson(serde_json::Error),
     Io(std::io::Error),
     Time(std::time::SystemTimeError),
+    BlockNotFound(u64),
+    PipelineStopped,
 }
 
 impl From<io::Error> for Error {
@@ -44,6 +48,11 @@ impl From<db::Error> for Error {
         Error::DB(err)
     }
 }
+impl From<eth::Error> for Error {
+    fn from(err: eth::Error) -> Self {
+        Error::Eth(err)
+    }
+}
 impl From<std::time::SystemTimeError> for Error {
     fn from(err: std::time::SystemTimeError) -> Self {
         Error::Time(err)
@@ -1,4 +1,7 @@
 mod scrap;
+mod pipeline;
+mod clique;
 mod error;
+mod tests;
 
-pub use self::scrap::start_scrapper;
\ No newline at end of file
+pub use self::scrap::{fill_gaps, reindex, start_scrapper};
\ No newline at end of file
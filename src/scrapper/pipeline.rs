@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use web3::futures::Future;
+use web3::types::{Block, BlockNumber, Transaction, TransactionReceipt, H256};
+use web3::Transport;
+
+use bootstrap::GETH_POW;
+use eth::{BatchReader, EthExt};
+use eth::types::{BlockExt, InternalTx, TxExt};
+use state::{GlobalState, Web3Client};
+
+use super::error::{Error, Result};
+
+/// a block and the data of its transactions, as retrieved from the node
+pub struct FetchedBlock {
+    pub block : Block<Transaction>,
+    pub receipts : Vec<TransactionReceipt>,
+    pub itxs : Option<Vec<Vec<InternalTx>>>,
+    pub tx_exts : Vec<TxExt>,
+    pub ext : Option<BlockExt>,
+    pub uncles : Vec<Block<H256>>,
+}
+
+/// The typed transaction fields of a block
+#[derive(Deserialize)]
+struct BlockTxExts {
+    transactions : Vec<TxExt>,
+}
+
+/// retrieve a block, its receipts and internal transactions
+pub fn fetch_block(gs: &GlobalState, wc: &Web3Client, br: &mut BatchReader, blockno: u64) -> Result<FetchedBlock> {
+
+    // read block and with its transactions, and the fields added after london
+    let value = wc
+        .web3
+        .transport()
+        .execute("eth_getBlockByNumber", vec![
+            web3::helpers::serialize(&BlockNumber::Number(blockno)),
+            web3::helpers::serialize(&true),
+        ])
+        .wait()?;
+    if value.is_null() {
+        return Err(Error::BlockNotFound(blockno));
+    }
+    let block : Block<Transaction> = serde_json::from_value(value.clone())?;
+    let tx_exts = serde_json::from_value::<BlockTxExts>(value.clone())?.transactions;
+    let ext = Some(serde_json::from_value::<BlockExt>(value)?)
+        .filter(|ext| *ext != BlockExt::default());
+
+    // read transaction receipts
+    let (receipts, receipt_exts) : (Vec<_>, Vec<_>) = br.receipts(wc.web3.transport(), &block)?
+        .into_iter()
+        .unzip();
+    let tx_exts = tx_exts.into_iter()
+        .zip(receipt_exts)
+        .map(|(tx_ext, receipt_ext)| tx_ext.with_receipt(receipt_ext))
+        .collect();
+
+    // read internal transactions
+    let itxs = if gs.cfg.db_store_itx && gs.cfg.web3_itx {
+        Some(br.internal_txs(wc.web3.transport(), &block)?)
+    } else {
+        None
+    };
+
+    // read the uncles, to compute the rewards
+    let mut uncles = Vec::new();
+    if gs.cfg.web3_client == GETH_POW {
+        let eth_ext : EthExt<_> = wc.web3.api();
+        for index in 0..block.uncles.len() as u64 {
+            uncles.push(eth_ext.uncle(blockno, index).wait()?.ok_or(Error::BlockNotFound(blockno))?);
+        }
+    }
+
+    Ok(FetchedBlock { block, receipts, itxs, tx_exts, ext, uncles })
+}
+
+/// Prefetches blocks with a pool of workers, and returns them in the
+///   same order they were requested. The workers are kept between scans, so
+///   their web3 clients and batch readers are reused
+pub struct Pipeline {
+    blocks : Box<dyn Iterator<Item=u64> + Send>,
+    jobs : Sender<(u64,u64)>,
+    results : Receiver<(u64,Result<FetchedBlock>)>,
+    fetched : BTreeMap<u64,Result<FetchedBlock>>,
+    scheduled : u64,
+    delivered : u64,
+    window : u64,
+}
+
+impl Pipeline {
+
+    /// start the workers that will fetch the blocks
+    pub fn new(gs: &Arc<GlobalState>) -> Self {
+
+        let workers = gs.cfg.scan_workers.unwrap_or(1).max(1);
+
+        let (jobs, jobs_rx) = channel::<(u64,u64)>();
+        let (results_tx, results) = channel();
+        let jobs_rx = Arc::new(Mutex::new(jobs_rx));
+
+        for _ in 0..workers {
+            let gs = gs.clone();
+            let jobs_rx = jobs_rx.clone();
+            let results_tx = results_tx.clone();
+            thread::spawn(move || {
+                let wc = gs.new_web3client();
+                let mut br = BatchReader::new(gs.cfg.trace_api());
+                loop {
+                    // the lock is released after getting the job
+                    let job = jobs_rx.lock().unwrap().recv();
+                    match job {
+                        Ok((seq, blockno)) => {
+                            let fetched = fetch_block(&gs, &wc, &mut br, blockno);
+                            if results_tx.send((seq, fetched)).is_err() {
+                                break;
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                }
+            });
+        }
+
+        Pipeline {
+            blocks : Box::new(std::iter::empty()),
+            jobs,
+            results,
+            fetched : BTreeMap::new(),
+            scheduled : 0,
+            delivered : 0,
+            window : 4 * workers as u64,
+        }
+    }
+
+    /// set the blocks to fetch, discarding the ones prefetched and the ones
+    ///   being fetched for the previous blocks
+    pub fn feed(&mut self, blocks: Box<dyn Iterator<Item=u64> + Send>) {
+        self.blocks = blocks;
+        self.fetched.clear();
+        self.delivered = self.scheduled;
+    }
+
+    /// send to the workers the blocks that fit in the prefetch window
+    fn schedule(&mut self) {
+        while self.scheduled < self.delivered + self.window {
+            if let Some(blockno) = self.blocks.next() {
+                if self.jobs.send((self.scheduled, blockno)).is_err() {
+                    break;
+                }
+                self.scheduled += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// get the next fetched block, waiting for it if necessary
+    pub fn next(&mut self) -> Option<Result<FetchedBlock>> {
+        self.schedule();
+        if self.delivered == self.scheduled {
+            return None;
+        }
+        loop {
+            if let Some(fetched) = self.fetched.remove(&self.delivered) {
+                self.delivered += 1;
+                return Some(fetched);
+            }
+            match self.results.recv() {
+                Ok((seq, fetched)) => {
+                    if seq >= self.delivered {
+                        self.fetched.insert(seq, fetched);
+                    }
+                }
+                Err(_) => return Some(Err(Error::PipelineStopped)),
+            }
+        }
+    }
+}
@@ -1,22 +1,180 @@
+use db::{AddrEvent, AddrEventKind, BlockWriter};
 use state::{GlobalState, Web3Client};
 use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::{thread, time};
 use std::time::{Duration, SystemTime};
 
 use web3::futures::Future;
-use web3::types::{BlockId, BlockNumber, Transaction};
-
-use eth::geth;
+use web3::types::{Block, BlockId, BlockNumber, Transaction, H256, U256};
 
+use super::super::bootstrap::GETH_CLIQUE;
+use super::super::eth::subscribe_new_heads;
 use super::super::eth::types::*;
-use super::error::Result;
+use super::error::{Error, Result};
+use super::clique;
+use super::pipeline::{FetchedBlock, Pipeline};
+
+/// stage in the db writer the received data
+fn write_block(gs: &GlobalState, writer: &mut BlockWriter, fetched: FetchedBlock) -> Result<()> {
+
+    for (i, (tx, re)) in fetched.block.transactions.iter().zip(fetched.receipts.iter()).enumerate() {
+        if let Some(itxs) = &fetched.itxs {
+            writer.add_tx(&tx, &re, Some(&itxs[i]))?;
+        } else {
+            writer.add_tx(&tx, &re, None)?;
+        }
+        // chains before berlin have no typed fields
+        if let Some(ext) = fetched.tx_exts.get(i).filter(|ext| **ext != TxExt::default()) {
+            writer.add_tx_ext(&tx.hash, ext)?;
+        }
+    }
+
+    if let Some(ext) = &fetched.ext {
+        let blockno = fetched.block.number.unwrap().low_u64();
+        writer.add_block_ext(blockno, ext)?;
+        for withdrawal in &ext.withdrawals {
+            writer.add_addr_event(&withdrawal.address, &AddrEvent {
+                kind : AddrEventKind::Withdrawal,
+                blockno,
+                index : withdrawal.index.low_u64(),
+                value : withdrawal.amount * U256::from(1_000_000_000u64),
+            })?;
+        }
+    }
+
+    // uncles and the static rewards of the miners, the genesis block is not mined
+    if let Some(schedule) = &gs.reward_schedule {
+        let blockno = fetched.block.number.unwrap().low_u64();
+        if blockno > 0 {
+            writer.add_addr_event(&fetched.block.author, &AddrEvent {
+                kind : AddrEventKind::BlockReward,
+                blockno,
+                index : 0,
+                value : schedule.miner_reward(blockno, fetched.uncles.len()),
+            })?;
+        }
+        for (index, uncle) in fetched.uncles.iter().enumerate() {
+            writer.add_uncle(blockno, index as u64, uncle)?;
+            writer.add_addr_event(&uncle.author, &AddrEvent {
+                kind : AddrEventKind::UncleReward,
+                blockno,
+                index : index as u64,
+                value : schedule.uncle_reward(blockno, uncle.number.unwrap().low_u64()),
+            })?;
+        }
+    }
+
+    writer.add_block(&into_block(fetched.block, |tx: Transaction| tx.hash))?;
+
+    Ok(())
+}
+
+/// stage the removal of all the records written for a block
+fn rollback_block(gs: &GlobalState, wc: &Web3Client, writer: &mut BlockWriter, blockno: u64, hash: H256) -> Result<()> {
+
+    if let Some(block) = gs.db.get_block(blockno)? {
+
+        // transactions are stored in the db
+        for txhash in &block.transactions {
+            if let Some(tx) = gs.db.get_tx(&txhash)? {
+                let contract_address = gs.db.get_receipt(&txhash)?
+                    .and_then(|re| re.contract_address);
+                let mut itxs : Vec<InternalTx> = gs.db.iter_itxs(&txhash)
+                    .map(|(_,itx)| itx)
+                    .collect();
+                itxs.reverse();
+                writer.remove_tx(&tx, contract_address, Some(&itxs))?;
+            }
+        }
+
+    } else if !gs.cfg.db_store_addr || gs.db.has_block_txlinks(blockno)? {
+
+        // the indexed address links are removed with the block
+
+    } else if let Some(block) = wc.web3.eth().block_with_txs(BlockId::Hash(hash)).wait()? {
+
+        // indexed before the links were indexed by block, get the transactions
+        //   from the node
+        for tx in &block.transactions {
+            let contract_address = if tx.to.is_none() {
+                Some(contract_address(&tx.from, &tx.nonce))
+            } else {
+                None
+            };
+            writer.remove_tx(&tx, contract_address, None)?;
+        }
+
+    } else {
+        error!("Block 0x{:x} not found in node, its address links cannot be removed", hash);
+        return Err(Error::BlockNotFound(blockno));
+    }
+
+    writer.remove_block(blockno)?;
+
+    Ok(())
+}
+
+/// get the hash of the block in the canonical chain of the node
+fn canonical_hash(wc: &Web3Client, blockno: u64) -> Result<Option<H256>> {
+    Ok(wc.web3.eth()
+        .block(BlockId::Number(BlockNumber::Number(blockno)))
+        .wait()?
+        .and_then(|b| b.hash))
+}
+
+/// if the block does not extend the indexed chain, rollback the orphaned blocks
+///   until the common ancestor and return the next block to scan. canonical
+///   gets the hash of a block in the canonical chain
+pub fn rollback_reorg<F>(gs: &GlobalState, wc: &Web3Client, block: &Block<Transaction>, canonical: F) -> Result<Option<u64>>
+where
+    F: Fn(u64) -> Result<Option<H256>>,
+{
+
+    let blockno = block.number.unwrap().low_u64();
+    if blockno == 0 {
+        return Ok(None);
+    }
+
+    match gs.db.get_block_hash(blockno-1)? {
+        Some(parent_hash) if parent_hash != block.parent_hash => {
+            warn!("Chain reorganization detected at block {}", blockno);
+        }
+        _ => return Ok(None),
+    }
+
+    // walk back until the indexed block matches with the canonical one
+    let mut next_block = blockno;
+    while next_block > 0 {
+        let n = next_block - 1;
+        let indexed = match gs.db.get_block_hash(n)? {
+            Some(hash) => hash,
+            None => break,
+        };
+        if canonical(n)? == Some(indexed) {
+            break;
+        }
+
+        warn!("Rolling back orphaned block {} 0x{:x}", n, indexed);
+        let mut writer = gs.db.writer();
+        rollback_block(gs, wc, &mut writer, n, indexed)?;
+        writer.remove_clique_block(n)?;
+        next_block = n;
+        writer.set_next_block_to_scan(next_block)?;
+        writer.commit()?;
+    }
+
+    info!("Reindexing from block {}", next_block);
+    Ok(Some(next_block))
+}
 
-/// scan the blockchain 
-fn scrap_blocks(gs: &GlobalState, wc: &Web3Client) -> Result<()>{
+/// scan the blockchain
+fn scrap_blocks(gs: &Arc<GlobalState>, wc: &Web3Client, pipeline: &mut Pipeline) -> Result<()>{
 
     // get next block to scan
     let mut next_block = gs.db.get_next_block_to_scan()?.unwrap();
-    
+
     if let Some(cfg_start_block) = gs.cfg.scan_start_block {
         if cfg_start_block > next_block {
             next_block = cfg_start_block;
@@ -25,12 +183,23 @@ fn scrap_blocks(gs: &GlobalState, wc: &Web3Client) -> Result<()>{
 
     // loop until last block number or stop_signal
     let mut last_output = SystemTime::UNIX_EPOCH;
-    let until_block = wc.web3.eth().block_number().wait()?.low_u64();
+    let until_block = wc.web3.eth().block_number().wait()?.low_u64()
+        .saturating_sub(gs.cfg.scan_confirmations.unwrap_or(0));
+    pipeline.feed(Box::new(next_block..=until_block));
+
+    // clique signers are tracked in the same order that blocks are added
+    let track_clique = gs.cfg.web3_client == GETH_CLIQUE;
+    let mut snapshot = if track_clique && next_block <= until_block {
+        Some(clique::load_snapshot(gs, wc, next_block)?)
+    } else {
+        None
+    };
+
     while next_block <= until_block && !gs.stop_signal.load(Ordering::SeqCst) {
 
         // show progress
         if SystemTime::now().duration_since(last_output)? > Duration::from_secs(5) {
-            let progress = (next_block * 1000) / until_block;
+            let progress = (next_block * 1000) / until_block.max(1);
             info!(
                 "Adding block {}/{} ({}‰)...",
                 next_block, until_block, progress
@@ -38,50 +207,180 @@ fn scrap_blocks(gs: &GlobalState, wc: &Web3Client) -> Result<()>{
             last_output = SystemTime::now();
         }
 
-        // read block and with its transactions
-        let block = wc
-            .web3
-            .eth()
-            .block_with_txs(BlockId::Number(BlockNumber::Number(next_block)))
-            .wait()?
-            .unwrap();
+        let fetched = match pipeline.next() {
+            Some(fetched) => fetched?,
+            None => break,
+        };
 
-        // process each transaction
-        for tx in &block.transactions {
+        // if the chain has been reorganized, reindex from the common ancestor
+        //   discarding the prefetched blocks
+        if let Some(ancestor_next_block) = rollback_reorg(gs, wc, &fetched.block, |n| canonical_hash(wc, n))? {
+            next_block = ancestor_next_block;
+            pipeline.feed(Box::new(next_block..=until_block));
+            if track_clique {
+                snapshot = Some(clique::load_snapshot(gs, wc, next_block)?);
+            }
+            continue;
+        }
 
-            // read transaction receipt
-            let re = wc.web3.eth().transaction_receipt(tx.hash).wait()?.unwrap();
-            
-            // read internal transactions
-            if gs.cfg.db_store_itx && gs.cfg.web3_itx {
-                let dbg : geth::web3::Debug<_> = wc.web3.api();
-                let itxs = dbg.internal_txs(&tx).wait()?.parse()?;
-                gs.db.add_tx(&tx, &re, Some(&itxs))?;
-            } else {
-                gs.db.add_tx(&tx, &re, None)?;
-            };
+        // write the block and move the cursor atomically
+        let mut writer = gs.db.writer();
+        if let Some(snapshot) = &mut snapshot {
+            clique::track_block(gs, &mut writer, snapshot, &fetched.block)?;
+            writer.set_clique_snapshot(snapshot)?;
         }
+        write_block(gs, &mut writer, fetched)?;
+        next_block += 1;
+        writer.set_next_block_to_scan(next_block)?;
+        writer.commit()?;
+    }
+    Ok(())
+}
 
-        // write to the db the receieved data
-        gs.db
-            .add_block(&into_block(block, |tx: Transaction| tx.hash))?;
+/// scan the blockchain backwards, from the first scanned block to scan_start_block,
+///   for a while. Returns true if there are still blocks to scan
+fn scrap_blocks_backwards(gs: &Arc<GlobalState>, wc: &Web3Client, pipeline: &mut Pipeline) -> Result<bool>{
 
-        // process next block
-        next_block += 1;
-        gs.db.set_next_block_to_scan(next_block)?;
+    let start_block = gs.cfg.scan_start_block.unwrap_or(1);
+    let mut first_block = gs.db.get_first_scanned_block()?.unwrap();
+
+    // scan for a while, so new blocks are scanned forward in time
+    let started = SystemTime::now();
+    pipeline.feed(Box::new((start_block..first_block).rev()));
+    while first_block > start_block
+        && !gs.stop_signal.load(Ordering::SeqCst)
+        && SystemTime::now().duration_since(started)? < Duration::from_secs(5) {
+
+        let fetched = match pipeline.next() {
+            Some(fetched) => fetched?,
+            None => break,
+        };
+        let mut writer = gs.db.writer();
+        write_block(gs, &mut writer, fetched)?;
+        first_block -= 1;
+        writer.set_first_scanned_block(first_block)?;
+        writer.commit()?;
+    }
+
+    if first_block > start_block {
+        info!("Scanned backwards until block {}", first_block);
+    } else if gs.cfg.web3_client == GETH_CLIQUE {
+        // clique signers are tracked forward, once all the blocks are scanned
+        clique::replay_backwards(gs, wc, start_block)?;
+    }
+
+    Ok(first_block > start_block)
+}
+
+/// reindex the blocks, replacing the records already stored for them
+fn reindex_blocks(gs: &Arc<GlobalState>, blocks: Vec<u64>) -> Result<()> {
+
+    let wc = gs.new_web3client();
+    let count = blocks.len();
+    let mut last_output = SystemTime::UNIX_EPOCH;
+    let mut pipeline = Pipeline::new(gs);
+    pipeline.feed(Box::new(blocks.into_iter()));
+    let mut done = 0;
+    while !gs.stop_signal.load(Ordering::SeqCst) {
+
+        let fetched = match pipeline.next() {
+            Some(fetched) => fetched?,
+            None => break,
+        };
+        let blockno = fetched.block.number.unwrap().low_u64();
+
+        if SystemTime::now().duration_since(last_output)? > Duration::from_secs(5) {
+            info!("Reindexing block {} ({}/{})...", blockno, done, count);
+            last_output = SystemTime::now();
+        }
+
+        // remove the old records and add the new ones atomically, counters are
+        //   only updated for the records that are really added or removed
+        let mut writer = gs.db.writer();
+        if let Some(hash) = gs.db.get_block_hash(blockno)? {
+            rollback_block(gs, &wc, &mut writer, blockno, hash)?;
+        }
+        write_block(gs, &mut writer, fetched)?;
+        writer.commit()?;
+        done += 1;
     }
+
+    info!("Reindexed {}/{} blocks", done, count);
     Ok(())
 }
 
+/// reindex the blocks in the range [from,to]
+pub fn reindex(gs: &Arc<GlobalState>, from: u64, to: u64) -> Result<()> {
+    reindex_blocks(gs, (from..=to).collect())
+}
+
+/// index the blocks between the first and next scanned blocks that are missing
+pub fn fill_gaps(gs: &Arc<GlobalState>) -> Result<()> {
+    let first_block = gs.db.get_first_scanned_block()?.unwrap_or(1);
+    let next_block = gs.db.get_next_block_to_scan()?.unwrap_or(first_block);
+    let missing = gs.db.missing_blocks(first_block, next_block)?;
+    info!("Found {} missing blocks between {} and {}", missing.len(), first_block, next_block);
+    reindex_blocks(gs, missing)
+}
+
+/// wait until a new head is notified, or 5 seconds if there is no subscription
+fn wait_new_head(heads: &mut Option<Receiver<u64>>) {
+    let disconnected = if let Some(rx) = heads {
+        match rx.recv_timeout(time::Duration::from_secs(5)) {
+            Ok(_) => {
+                // all pending notifications are handled in the same scan
+                while rx.try_recv().is_ok() {}
+                false
+            }
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => true,
+        }
+    } else {
+        thread::sleep(time::Duration::from_secs(5));
+        false
+    };
+    if disconnected {
+        warn!("newHeads subscription finished, polling for new blocks");
+        *heads = None;
+    }
+}
+
 /// scan the blockchain until the stop_signal is recieved
-pub fn start_scrapper(gs: &GlobalState) {
+pub fn start_scrapper(gs: Arc<GlobalState>) {
     let wc = gs.new_web3client();
+    let mut pipeline = Pipeline::new(&gs);
+    let mut heads = subscribe_new_heads(&gs.cfg.web3_urls());
+    let mut pending_backwards = false;
+
+    let mut last_health_check = SystemTime::UNIX_EPOCH;
 
     while !gs.stop_signal.load(Ordering::SeqCst) {
-        thread::sleep(time::Duration::from_secs(5));
-        if let Err(err) = scrap_blocks(&gs, &wc) {
+        if !pending_backwards {
+            wait_new_head(&mut heads);
+        }
+
+        // go back to the preferred web3 endpoint when it is healthy again
+        if gs.cfg.web3_fallback_urls.is_some()
+            && SystemTime::now().duration_since(last_health_check).unwrap_or_default() > Duration::from_secs(60) {
+            for (url, healthy) in wc.web3.transport().health_check() {
+                if !healthy {
+                    warn!("web3 endpoint {} is not responding", url);
+                }
+            }
+            last_health_check = SystemTime::now();
+        }
+        if let Err(err) = scrap_blocks(&gs, &wc, &mut pipeline) {
             error!("Scan result failed: {:?}", err);
         }
+        if gs.cfg.scan_backwards.unwrap_or(false) {
+            pending_backwards = match scrap_blocks_backwards(&gs, &wc, &mut pipeline) {
+                Ok(pending) => pending,
+                Err(err) => {
+                    error!("Backwards scan result failed: {:?}", err);
+                    false
+                }
+            };
+        }
     }
 
     info!("Finished scanning transactions, self-killing.");
@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod tests {
+    use web3::types::{Address, Block, Bytes, Transaction, TransactionReceipt, H2048, H256, U128, U256};
+
+    use super::super::super::eth::FailoverTransport;
+    use super::super::super::eth::geth::clique::Snapshot;
+    use super::super::super::fixtures::{block, block_with, test_config, test_state};
+    use super::super::super::state::{GlobalState, Web3Client};
+    use super::super::clique::load_snapshot;
+    use super::super::scrap::rollback_reorg;
+
+    /// create a state with an empty database that only stores the address links
+    fn init() -> GlobalState {
+        let mut cfg = test_config();
+        cfg.db_store_itx = false;
+        cfg.db_store_tx = false;
+        test_state(cfg)
+    }
+
+    /// a client for a node that is not running, the rollback must not need it
+    fn offline_client(gs: &GlobalState) -> Web3Client {
+        let (eloops, transport) = FailoverTransport::new(&gs.cfg.web3_urls()).unwrap();
+        Web3Client { eloops, web3: web3::Web3::new(transport.without_backoff()) }
+    }
+
+    fn snapshot(number: u64, signers: &[Address]) -> Snapshot {
+        Snapshot { number, signers: signers.to_vec(), votes: Vec::new(), recents: Vec::new() }
+    }
+
+    fn tx(number: u64, hash: H256, from: Address, to: Address) -> (Transaction, TransactionReceipt) {
+        let tx = Transaction {
+            hash,
+            nonce: U256::zero(),
+            block_hash: None,
+            block_number: Some(U256::from(number)),
+            transaction_index: Some(U128::zero()),
+            from,
+            to: Some(to),
+            value: U256::one(),
+            gas_price: U256::one(),
+            gas: U256::one(),
+            input: Bytes(Vec::new()),
+        };
+        let receipt = TransactionReceipt {
+            block_hash: None,
+            block_number: tx.block_number,
+            transaction_index: U128::zero(),
+            contract_address: None,
+            gas_used: Some(U256::one()),
+            cumulative_gas_used: U256::one(),
+            status: None,
+            transaction_hash: hash,
+            logs: Vec::new(),
+            logs_bloom: H2048::default(),
+        };
+        (tx, receipt)
+    }
+
+    #[test]
+    fn test_rollback_reorg_without_txs() {
+        let gs = init();
+        let wc = offline_client(&gs);
+        let a1 = Address::from(0xa1);
+        let a2 = Address::from(0xa2);
+
+        // blocks 1 and 2 are indexed, with a transaction each
+        let mut writer = gs.db.writer();
+        for number in 1..=2 {
+            let mut b : Block<H256> = block(number);
+            let (tx, receipt) = tx(number, H256::from(0x100 + number), a1, a2);
+            b.transactions.push(tx.hash);
+            writer.add_block(&b).unwrap();
+            writer.add_tx(&tx, &receipt, None).unwrap();
+            writer.set_clique_snapshot(&snapshot(number, &[a1])).unwrap();
+        }
+        writer.set_next_block_to_scan(3).unwrap();
+        writer.commit().unwrap();
+        assert_eq!(Ok(2), gs.db.count_addr_tx_links(&a1));
+        assert_eq!(Ok(true), gs.db.has_block_txlinks(2));
+
+        // the new block 3 extends the indexed chain
+        let head : Block<Transaction> = block(3);
+        let reorg = rollback_reorg(&gs, &wc, &head, |n| Ok(Some(H256::from(n)))).unwrap();
+        assert_eq!(None, reorg);
+
+        // block 2 has been replaced by 0x22
+        let head : Block<Transaction> = block_with(3, json!({
+            "hash": H256::from(0x33),
+            "parentHash": H256::from(0x22),
+        }));
+        let reorg = rollback_reorg(&gs, &wc, &head, |n| {
+            Ok(Some(if n == 2 { H256::from(0x22) } else { H256::from(n) }))
+        }).unwrap();
+        assert_eq!(Some(2), reorg);
+
+        assert_eq!(Ok(Some(2)), gs.db.get_next_block_to_scan());
+        assert_eq!(Ok(None), gs.db.get_block_hash(2));
+        assert_eq!(Ok(Some(H256::from(1))), gs.db.get_block_hash(1));
+        assert_eq!(Ok(false), gs.db.has_block_txlinks(2));
+        assert_eq!(Ok(1), gs.db.count_addr_tx_links(&a1));
+        assert_eq!(Ok(1), gs.db.count_addr_tx_links(&a2));
+        assert_eq!(1, gs.db.iter_addr_tx_links(&a1).count());
+
+        // the clique signers are rewound to the common ancestor, so they are
+        //   loaded without replaying the headers from the offline node
+        assert_eq!(Ok(Some(snapshot(1, &[a1]))), gs.db.get_clique_snapshot());
+        assert_eq!(snapshot(1, &[a1]), load_snapshot(&gs, &wc, 2).unwrap());
+    }
+}
@@ -1,21 +1,48 @@
 #![cfg_attr(feature = "cargo-clippy", allow(clippy::new_ret_no_self))]
 
 use db;
+use eth;
 
 use std::collections::HashMap;
-use web3::types::{Address};
+use std::fs;
+use std::io;
+use web3::types::{Address, H256};
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::AtomicBool;
-use bootstrap::{Config,load_handlebars_templates};
+use bootstrap::{Config,load_handlebars_templates,GETH_POW};
 use handlebars::Handlebars;
 use eth::types::hex_to_addr;
+use eth::FailoverTransport;
+use eth::genesis::{read_genesis_alloc, read_genesis_forks};
+use eth::geth::ethash::{RewardSchedule, MAINNET_BYZANTIUM, MAINNET_CONSTANTINOPLE};
 use web3::futures::Future;
 
 #[derive(Debug)]
 pub enum Error {
     Web3(web3::Error),
     FromHex(rustc_hex::FromHexError),
+    Io(io::Error),
+    Eth(eth::Error),
+    Db(db::Error),
+    DbNotFound(String),
+    UnknownForks(String),
 }
 
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+impl From<eth::Error> for Error {
+    fn from(err: eth::Error) -> Self {
+        Error::Eth(err)
+    }
+}
+impl From<db::Error> for Error {
+    fn from(err: db::Error) -> Self {
+        Error::Db(err)
+    }
+}
 impl From<web3::Error> for Error {
     fn from(err: web3::Error) -> Self {
         Error::Web3(err)
@@ -35,21 +62,97 @@ pub struct GlobalState {
     pub cfg: Config,
     pub hb: Handlebars,
     pub named_address : HashMap<Address,String>,
+    pub reward_schedule : Option<RewardSchedule>,
+    /// the last opcode-level trace, so its pages are not traced again
+    pub last_trace : Mutex<Option<(H256,Arc<eth::geth::web3::DbgStructLogs>)>>,
 }
 
 pub struct Web3Client {
-    pub eloop: web3::transports::EventLoopHandle,
-    pub web3: web3::Web3<web3::transports::Http>,
+    pub eloops: Vec<web3::transports::EventLoopHandle>,
+    pub web3: web3::Web3<FailoverTransport>,
+}
+
+/// get the network id and the head from the node
+fn node_network_id_and_head(cfg: &Config) -> web3::Result<(String,u64)> {
+    let (_eloops, transport) = FailoverTransport::new(&cfg.web3_urls())?;
+    let web3 = web3::Web3::new(transport.without_backoff());
+    let network_id = web3.net().version().wait()?;
+    let head = web3.eth().block_number().wait()?.low_u64();
+    Ok((network_id, head))
+}
+
+/// path of the file with the network id of the last node, next to the databases
+fn network_id_path(db_path: &str) -> String {
+    format!("{}.network_id",db_path)
+}
+
+/// find the database of the last network when the node is not available
+fn offline_db_path(db_path: &str) -> Result<String> {
+    match fs::read_to_string(network_id_path(db_path)) {
+        Ok(network_id) => Ok(format!("{}{}",db_path,network_id.trim())),
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound =>
+            Err(Error::DbNotFound(db_path.to_string())),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// get the ethash block reward schedule, the fork blocks are read from the
+///   config, then from the genesis config. Only mainnet has default blocks
+fn reward_schedule(cfg: &Config, network_id: Option<&str>) -> Result<RewardSchedule> {
+    let mut byzantium = cfg.pow_byzantium_block;
+    let mut constantinople = cfg.pow_constantinople_block;
+
+    // forks missing in the genesis config are not activated
+    if let Some(path) = &cfg.genesis_path {
+        let forks = read_genesis_forks(path)?;
+        byzantium = byzantium
+            .or(Some(forks.byzantium_block.unwrap_or(std::u64::MAX)));
+        constantinople = constantinople
+            .or(Some(forks.constantinople_block.or(forks.petersburg_block).unwrap_or(std::u64::MAX)));
+    }
+    if network_id == Some("1") {
+        byzantium = byzantium.or(Some(MAINNET_BYZANTIUM));
+        constantinople = constantinople.or(Some(MAINNET_CONSTANTINOPLE));
+    }
+
+    match (byzantium, constantinople) {
+        (Some(byzantium), Some(constantinople)) => Ok(RewardSchedule { byzantium, constantinople }),
+        _ => Err(Error::UnknownForks(network_id.unwrap_or("unknown").to_string())),
+    }
+}
+
+/// import the genesis allocations, if they were not imported yet
+fn import_genesis(db: &db::AppDB, path: &str) -> Result<()> {
+    if db.count_genesis_allocs()? > 0 {
+        return Ok(());
+    }
+    let allocs = read_genesis_alloc(path)?;
+    info!("Importing {} genesis allocations", allocs.len());
+    let mut writer = db.writer();
+    for (addr, alloc) in &allocs {
+        writer.add_genesis_alloc(addr, alloc)?;
+    }
+    writer.commit()?;
+    Ok(())
 }
 
 impl GlobalState {
     
     pub fn new(cfg: Config) -> Result<Self>  {
         
-        let transport = web3::transports::Http::new(cfg.web3_url.as_str())?;
-        let web3 = web3::Web3::new(transport.1);
-        let network_id = web3.net().version().wait()?;
-        info!("Network id is {}",network_id);
+        // if the node is not available, serve from the local database
+        let node = node_network_id_and_head(&cfg);
+        let db_path = match &node {
+            Ok((network_id,_)) => {
+                info!("Network id is {}",network_id);
+                fs::write(network_id_path(&cfg.db_path), network_id)?;
+                format!("{}{}",cfg.db_path,network_id)
+            }
+            Err(err) => {
+                warn!("Cannot connect to node ({}), running in offline mode", err);
+                offline_db_path(&cfg.db_path)?
+            }
+        };
 
         let mut hb = Handlebars::new();
         load_handlebars_templates(&mut hb);
@@ -59,7 +162,7 @@ impl GlobalState {
 
         // load database & init if not
         let db = db::AppDB::open_default(
-            &format!("{}{}",cfg.db_path,network_id),
+            &db_path,
             db::Options {
                 store_itx : cfg.db_store_itx,
                 store_tx : cfg.db_store_tx,
@@ -68,12 +171,47 @@ impl GlobalState {
             }
         ).expect("cannot open database");
 
-        // set the last block if not set
+        match &node {
+            Ok((network_id,_)) => db.set_network_id(network_id)
+                .expect("error setting network id"),
+            Err(_) => if let Some(network_id) = db.get_network_id().expect("error reading network id") {
+                info!("Network id is {}",network_id);
+            },
+        }
+
+        // set the scan cursors if not set, when scanning backwards both start
+        //   from the last block, if not both start from scan_start_block
+        let start_block = cfg.scan_start_block.unwrap_or(1);
         if None == db.get_next_block_to_scan().expect("error reading last block") {
-            db.set_next_block_to_scan(cfg.scan_start_block.unwrap_or(1))
+            let next_block = if cfg.scan_backwards.unwrap_or(false) {
+                let (_, head) = node?;
+                head.saturating_sub(cfg.scan_confirmations.unwrap_or(0))
+                    .max(start_block)
+            } else {
+                start_block
+            };
+            db.set_next_block_to_scan(next_block)
                 .expect("error setting last block");
+            db.set_first_scanned_block(next_block)
+                .expect("error setting first block");
+        } else if None == db.get_first_scanned_block().expect("error reading first block") {
+            db.set_first_scanned_block(start_block)
+                .expect("error setting first block");
+        }
+
+        // record the genesis allocations as block 0
+        if let Some(path) = &cfg.genesis_path {
+            import_genesis(&db, path)?;
         }
 
+        // the ethash rewards depend on the forks of the network
+        let reward_schedule = if cfg.web3_client == GETH_POW {
+            let network_id = db.get_network_id()?;
+            Some(reward_schedule(&cfg, network_id.as_ref().map(|id| id.as_str()))?)
+        } else {
+            None
+        };
+
         // read named addresses
         let mut named_address = HashMap::new();
         if let Some(nas) = &cfg.named_address {
@@ -82,14 +220,27 @@ impl GlobalState {
             }        
         }
 
-        Ok(GlobalState { cfg, db, hb, stop_signal, named_address })
+        let last_trace = Mutex::new(None);
+
+        Ok(GlobalState { cfg, db, hb, stop_signal, named_address, reward_schedule, last_trace })
     }
+    /// create a web3 client for the explorer, that fails fast if the node is not
+    ///   available
+    pub fn try_new_web3client(&self) -> Option<Web3Client> {
+        FailoverTransport::new(&self.cfg.web3_urls())
+            .map(|(eloops, transport)| Web3Client {
+                eloops,
+                web3: web3::Web3::new(transport.without_backoff()),
+            })
+            .ok()
+    }
+
     pub fn new_web3client(&self) -> Web3Client {
-        let (eloop, transport) = web3::transports::Http::new(self.cfg.web3_url.as_str())
-            .expect("opening http connection");
+        let (eloops, transport) = FailoverTransport::new(&self.cfg.web3_urls())
+            .expect("opening web3 connection");
 
         Web3Client {
-            eloop,
+            eloops,
             web3: web3::Web3::new(transport),
         }
     }